@@ -0,0 +1,53 @@
+#![feature(f128)]
+
+//! Times turning, projecting and drawing the larger 4d polytopes on one thread against the
+//! whole thread pool. Run with `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rayon::ThreadPoolBuilder;
+
+use cubers::{mesh::fourd::Mesh as Mesh4d, screen::Screen, shape::fourd::Shape as Shape4d};
+
+type Frame = Screen<78, 42>;
+
+/// One frame of an animation: turns the shape, projects it to 3d and draws it
+fn frame(screen: &mut Frame, shape: &Mesh4d, scale: f128) {
+    let mesh = shape
+        .clone()
+        .rotate_xw_theta(0.3)
+        .rotate_yz_theta(0.2)
+        .to_mesh_3d()
+        * scale;
+    screen.clear_screen();
+    screen.render_mesh(&mesh);
+}
+
+fn render(c: &mut Criterion) {
+    let shapes = [
+        ("120-cell", Shape4d::hundredtwentycell()),
+        ("600-cell", Shape4d::sixhundredcell()),
+    ];
+    let mut threads = vec![1, rayon::current_num_threads()];
+    threads.dedup();
+    let mut group = c.benchmark_group("frame");
+    group.sample_size(10);
+    for (name, shape) in shapes {
+        let shape = Mesh4d::from(shape);
+        let screen = Frame::default();
+        let scale = screen.fit_scale(&[shape.to_mesh_3d()]);
+        for &threads in &threads {
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            group.bench_with_input(BenchmarkId::new(name, threads), &shape, |b, shape| {
+                let mut screen = screen;
+                b.iter(|| pool.install(|| frame(&mut screen, shape, scale)));
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -0,0 +1,211 @@
+use std::f128;
+
+use serde::Deserialize;
+
+use crate::{
+    line::{fourd::Line as Line4d, Line},
+    matrix::fourd::Matrix4x4,
+    point::{fourd::Point as Point4d, Point},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Projection {
+    Perspective,
+    Orthographic,
+}
+
+/// A viewer in 3d space. The camera looks from `position` towards `target`, with `up` giving
+/// the direction that is drawn towards the top of the screen.
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    pub position: Point,
+    pub target: Point,
+    pub up: Point,
+    /// Vertical field of view in radians
+    pub fov: f128,
+    pub near: f128,
+    pub far: f128,
+    pub projection: Projection,
+}
+
+impl Default for Camera {
+    /// Looks along +y from y = -4 with z up
+    fn default() -> Self {
+        Camera {
+            position: Point([0., -4., 0.]),
+            target: Point([0., 0., 0.]),
+            up: Point([0., 0., 1.]),
+            fov: f128::consts::FRAC_PI_3,
+            near: 0.1,
+            far: 100.,
+            projection: Projection::Perspective,
+        }
+    }
+}
+
+impl Camera {
+    /// Transforms world coordinates into camera coordinates, where the camera sits at the origin
+    /// looking down -z with +y up.
+    pub fn view(&self) -> Matrix4x4 {
+        let forward = (self.target - self.position).normalize();
+        let right = forward.cross(self.up).normalize();
+        let up = right.cross(forward);
+        let eye = self.position;
+        Matrix4x4([
+            [right[0], right[1], right[2], -right.dot(eye)],
+            [up[0], up[1], up[2], -up.dot(eye)],
+            [-forward[0], -forward[1], -forward[2], forward.dot(eye)],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Transforms camera coordinates into clip coordinates. `aspect` is the width of the
+    /// viewport divided by its height.
+    pub fn projection(&self, aspect: f128) -> Matrix4x4 {
+        let (near, far) = (self.near, self.far);
+        match self.projection {
+            Projection::Perspective => {
+                let focal = 1. / (self.fov / 2.).tan();
+                Matrix4x4([
+                    [focal / aspect, 0., 0., 0.],
+                    [0., focal, 0., 0.],
+                    [0., 0., (far + near) / (near - far), 2. * far * near / (near - far)],
+                    [0., 0., -1., 0.],
+                ])
+            }
+            Projection::Orthographic => {
+                // Sized so the target appears the same size as under perspective
+                let half_height = self.distance() * (self.fov / 2.).tan();
+                Matrix4x4([
+                    [1. / (aspect * half_height), 0., 0., 0.],
+                    [0., 1. / half_height, 0., 0.],
+                    [0., 0., -2. / (far - near), -(far + near) / (far - near)],
+                    [0., 0., 0., 1.],
+                ])
+            }
+        }
+    }
+
+    pub fn view_projection(&self, aspect: f128) -> Matrix4x4 {
+        self.projection(aspect) * self.view()
+    }
+
+    /// Projects a point into normalized device coordinates, where the visible region is
+    /// [-1, 1] on both axes. Points behind the near plane give meaningless results; use
+    /// `clip_line` for anything that might cross it
+    pub fn project(view_projection: &Matrix4x4, point: Point) -> (f128, f128) {
+        Self::perspective_divide(*view_projection * point.homogeneous())
+    }
+
+    /// Transforms a line into clip coordinates and clips it against the near plane. Returns
+    /// `None` if the line is entirely behind the near plane
+    pub fn clip_line(view_projection: &Matrix4x4, line: Line) -> Option<Line4d> {
+        Self::clip_near(Line4d(line.map(|point| *view_projection * point.homogeneous())))
+    }
+
+    /// Clips a line already in clip coordinates against the near plane
+    pub fn clip_near(line: Line4d) -> Option<Line4d> {
+        line.clip(|point| point[2] + point[3])
+    }
+
+    /// Turns clip coordinates into normalized device coordinates
+    pub fn perspective_divide(clip: Point4d) -> (f128, f128) {
+        (clip[0] / clip[3], clip[1] / clip[3])
+    }
+
+    /// Distance between the camera and its target
+    pub fn distance(&self) -> f128 {
+        (self.target - self.position).l2_norm()
+    }
+
+    /// Swing the camera around its target. `yaw` turns around the up vector, `pitch` tilts
+    /// over the top or bottom, stopping short of looking straight along the up vector.
+    pub fn orbit(&mut self, yaw: f128, pitch: f128) {
+        let offset = (self.position - self.target).rotate_about(self.up, yaw);
+        let right = offset.cross(self.up);
+        let pitched = offset.rotate_about(right, pitch);
+        let offset = if pitched.normalize().dot(self.up.normalize()).abs() < 0.99 {
+            pitched
+        } else {
+            offset
+        };
+        self.position = self.target + offset;
+    }
+
+    /// Move the camera towards (`factor` < 1) or away from (`factor` > 1) its target
+    pub fn dolly(&mut self, factor: f128) {
+        self.position = self.target + (self.position - self.target) * factor;
+    }
+
+    pub fn toggle_projection(&mut self) {
+        self.projection = match self.projection {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Perspective,
+        };
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::f128::consts::FRAC_PI_2;
+
+    use super::{Camera, Projection};
+    use crate::{
+        line::Line,
+        point::{fourd::Point as Point4d, Point},
+    };
+
+    #[test]
+    fn view_and_projection() {
+        let camera = Camera::default();
+        let target = camera.view() * camera.target.homogeneous();
+        let expected = [0., 0., -4., 1.];
+        assert!(target.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-9));
+
+        // The near and far planes are at the ends of normalized device depth
+        for projection in [Projection::Perspective, Projection::Orthographic] {
+            let camera = Camera { projection, ..camera };
+            let view_projection = camera.view_projection(2.);
+            let depth = |distance: f128| {
+                let clip = view_projection * Point([0., -4. + distance, 0.]).homogeneous();
+                clip[2] / clip[3]
+            };
+            assert!((depth(camera.near) + 1.).abs() < 1e-9);
+            assert!((depth(camera.far) - 1.).abs() < 1e-9);
+            assert_eq!(Camera::project(&view_projection, camera.target), (0., 0.));
+        }
+        assert_eq!(Camera::perspective_divide(Point4d([2., 4., 1., 2.])), (1., 2.));
+    }
+
+    #[test]
+    fn clip_line() {
+        let camera = Camera::default();
+        let view_projection = camera.view_projection(1.);
+        let line = Line([Point([0., -10., 0.]), camera.target]);
+        let clipped = Camera::clip_line(&view_projection, line).unwrap();
+        assert!((clipped[0][3] - camera.near).abs() < 1e-9);
+        assert_eq!(clipped[1].0, (view_projection * camera.target.homogeneous()).0);
+        assert!(Camera::clip_line(&view_projection, Line([Point([0., -10., 0.]); 2])).is_none());
+    }
+
+    #[test]
+    fn orbit_and_dolly() {
+        let mut camera = Camera::default();
+        camera.orbit(FRAC_PI_2, 0.);
+        assert!((camera.position - Point([4., 0., 0.])).l2_norm() < 1e-9);
+        camera.orbit(0., 0.3);
+        assert!((camera.distance() - 4.).abs() < 1e-9);
+        assert!(camera.position[2] != 0.);
+
+        // Pitching onto the up vector leaves the camera where it was
+        let before = camera.position;
+        camera.orbit(0., FRAC_PI_2 - 0.3);
+        assert_eq!(camera.position.0, before.0);
+
+        camera.dolly(0.5);
+        assert!((camera.distance() - 2.).abs() < 1e-9);
+        camera.dolly(3.);
+        assert!((camera.distance() - 6.).abs() < 1e-9);
+    }
+}
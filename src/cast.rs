@@ -0,0 +1,117 @@
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+/// Clears the terminal and moves the cursor to the top left
+const CLEAR: &str = "\x1b[2J\x1b[H";
+/// Moves the cursor to the top left, so each frame draws over the last
+const HOME: &str = "\x1b[H";
+
+/// Writes an asciinema v2 recording. Everything written to the recorder, for example by
+/// `Screen::print_screen_raw`, is collected until `frame` stamps it with a time and writes it
+/// out as one frame
+pub struct Recorder<W: Write> {
+    out: W,
+    pending: Vec<u8>,
+    frames: usize,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Starts a recording of a terminal `width` columns by `height` rows
+    pub fn new(mut out: W, width: usize, height: usize, title: &str) -> io::Result<Recorder<W>> {
+        writeln!(
+            out,
+            r#"{{"version": 2, "width": {width}, "height": {height}, "title": {}}}"#,
+            json_string(title)
+        )?;
+        Ok(Recorder {
+            out,
+            pending: vec![],
+            frames: 0,
+        })
+    }
+
+    /// Writes everything since the last frame as a frame shown `time` after the start
+    pub fn frame(&mut self, time: Duration) -> io::Result<()> {
+        let start = if self.frames == 0 { CLEAR } else { HOME };
+        let text = String::from_utf8_lossy(&self.pending);
+        writeln!(
+            self.out,
+            r#"[{:.6}, "o", {}]"#,
+            time.as_secs_f64(),
+            json_string(&format!("{start}{text}"))
+        )?;
+        self.pending.clear();
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Number of frames written so far
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Finishes the recording, returning the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> Write for Recorder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    /// Frames are only written by `frame`, so this just flushes the underlying writer
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// `text` as a quoted JSON string
+fn json_string(text: &str) -> String {
+    let mut json = String::from('"');
+    for char in text.chars() {
+        match char {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            '\r' => json += "\\r",
+            '\t' => json += "\\t",
+            char if char.is_control() => json += &format!("\\u{:04x}", char as u32),
+            char => json.push(char),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::{io::Write, time::Duration};
+
+    use super::Recorder;
+
+    #[test]
+    fn recording() {
+        let mut recorder = Recorder::new(vec![], 4, 2, "a \"test\"").unwrap();
+        write!(recorder, "ab  \r\n\x1b[38;2;1;2;3mcd\x1b[39m  \r\n").unwrap();
+        recorder.frame(Duration::ZERO).unwrap();
+        write!(recorder, "\\   \r\n    \r\n").unwrap();
+        recorder.frame(Duration::from_millis(50)).unwrap();
+        assert_eq!(recorder.frames(), 2);
+
+        let cast = String::from_utf8(recorder.finish().unwrap()).unwrap();
+        let lines = cast.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], r#"{"version": 2, "width": 4, "height": 2, "title": "a \"test\""}"#);
+        assert_eq!(
+            lines[1],
+            r#"[0.000000, "o", "\u001b[2J\u001b[Hab  \r\n\u001b[38;2;1;2;3mcd\u001b[39m  \r\n"]"#
+        );
+        assert_eq!(lines[2], r#"[0.050000, "o", "\u001b[H\\   \r\n    \r\n"]"#);
+        assert_eq!(lines.len(), 3);
+    }
+}
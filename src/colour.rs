@@ -0,0 +1,49 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer};
+
+/// A 24-bit RGB colour, written as `#rrggbb`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Colour(pub [u8; 3]);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColourError(pub String);
+
+impl Display for ParseColourError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "expected a colour like #ff8800, found {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseColourError {}
+
+impl Display for Colour {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [r, g, b] = self.0;
+        write!(f, "#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+impl FromStr for Colour {
+    type Err = ParseColourError;
+
+    /// Reads `#rrggbb`, with or without the `#`
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || ParseColourError(text.to_string());
+        let hex = text.strip_prefix('#').unwrap_or(text);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(error());
+        }
+        let channel =
+            |i: usize| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| error());
+        Ok(Colour([channel(0)?, channel(1)?, channel(2)?]))
+    }
+}
+
+impl<'de> Deserialize<'de> for Colour {
+    /// Reads the same text as `from_str`
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+/// User settings that persist between runs, stored as `key = value` lines
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Config {
+    /// Height of a terminal character cell divided by its width
+    pub char_ratio: f128,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config { char_ratio: 5. / 3. }
+    }
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/cubers/config`, falling back to `~/.config/cubers/config`
+    pub fn path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(base.join("cubers").join("config"))
+    }
+
+    /// Loads the config file, using defaults for anything missing or malformed
+    pub fn load() -> Config {
+        Config::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| Config::parse(&contents))
+            .unwrap_or_default()
+    }
+
+    pub fn parse(contents: &str) -> Config {
+        let mut config = Config::default();
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            if key.trim() == "char_ratio" {
+                if let Ok(ratio) = value.trim().parse::<f64>() {
+                    if ratio > 0. {
+                        config.char_ratio = ratio as f128;
+                    }
+                }
+            }
+        }
+        config
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Config::path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = fs::File::create(path)?;
+        writeln!(file, "char_ratio = {}", self.char_ratio as f64)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::Config;
+
+    #[test]
+    fn parse() {
+        assert_eq!(Config::parse("char_ratio = 2.5\n").char_ratio, 2.5);
+        assert_eq!(Config::parse("  char_ratio=2\n").char_ratio, 2.);
+
+        // Unknown keys, malformed lines and bad values are skipped, keeping the rest
+        let config = Config::parse("colour = red\nchar_ratio 3\nchar_ratio = 1.5\n");
+        assert_eq!(config.char_ratio, 1.5);
+        assert_eq!(Config::parse("char_ratio = wide\n"), Config::default());
+        assert_eq!(Config::parse("char_ratio = -1\n"), Config::default());
+        assert_eq!(Config::parse(""), Config::default());
+    }
+}
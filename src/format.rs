@@ -0,0 +1,339 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    mesh::{fourd::Mesh as Mesh4d, Mesh, Model},
+    shape::{fourd::Shape as Shape4d, Shape},
+    wythoff::Polytope,
+};
+
+pub mod native;
+pub mod obj;
+pub mod off;
+pub mod stl;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The file extension is not one of the formats that can be read or written
+    UnknownFormat(PathBuf),
+    /// The format can only hold 3d meshes
+    NotFourDimensional(PathBuf),
+    /// Something unexpected on a line of a text file, counting from 1
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    /// A face, line or cell refers to something that does not exist
+    Index {
+        line: usize,
+        index: i64,
+    },
+    /// The file ended before all of the data its header promised
+    Truncated,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{err}"),
+            Error::UnknownFormat(_) => write!(
+                f,
+                "cannot tell the format from the extension, expected .cubers, .obj, .off, .4off or .stl"
+            ),
+            Error::NotFourDimensional(path) => {
+                let extension = path.extension().unwrap_or_default().to_string_lossy();
+                write!(f, ".{extension} files can only hold 3d meshes")
+            }
+            Error::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            Error::Index { line, index } => {
+                write!(f, "line {line}: there is no vertex, face or cell {index}")
+            }
+            Error::Truncated => write!(f, "the file is shorter than its header says"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<Model> for Polytope {
+    fn from(model: Model) -> Self {
+        match model {
+            Model::Mesh(mesh) => Polytope::Shape(Shape::from(&mesh)),
+            Model::Mesh4d(mesh) => Polytope::Shape4d(Shape4d::from(&mesh)),
+        }
+    }
+}
+
+impl From<Polytope> for Model {
+    fn from(polytope: Polytope) -> Self {
+        match polytope {
+            Polytope::Shape(shape) => Model::Mesh(Mesh::from(shape)),
+            Polytope::Shape4d(shape) => Model::Mesh4d(Mesh4d::from(shape)),
+        }
+    }
+}
+
+/// Reads a mesh from a file, choosing the format from its extension
+pub fn load(path: &Path) -> Result<Model, Error> {
+    match extension(path).as_deref() {
+        Some("cubers") => native::parse(&fs::read_to_string(path)?),
+        Some("obj") => Ok(Model::Mesh(obj::parse(&fs::read_to_string(path)?)?)),
+        Some("off" | "4off") => off::parse(&fs::read_to_string(path)?),
+        Some("stl") => Ok(Model::Mesh(stl::parse(&fs::read(path)?)?)),
+        _ => Err(Error::UnknownFormat(path.to_path_buf())),
+    }
+}
+
+/// Writes a mesh to a file, choosing the format from its extension
+pub fn save(path: &Path, model: &Model) -> Result<(), Error> {
+    let extension = extension(path);
+    match (extension.as_deref(), model) {
+        (Some("cubers" | "off" | "4off"), _) | (Some("obj"), Model::Mesh(_)) => (),
+        (Some("obj"), Model::Mesh4d(_)) => {
+            return Err(Error::NotFourDimensional(path.to_path_buf()))
+        }
+        _ => return Err(Error::UnknownFormat(path.to_path_buf())),
+    }
+    let mut out = BufWriter::new(File::create(path)?);
+    match (extension.as_deref(), model) {
+        (Some("cubers"), _) => native::write(model, &mut out)?,
+        (Some("obj"), Model::Mesh(mesh)) => obj::write(mesh, &mut out)?,
+        _ => off::write(model, &mut out)?,
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase)
+}
+
+/// The sides of each polygon, and of each polyline in `lines`, with repeats removed
+fn edges<'a>(
+    faces: impl IntoIterator<Item = &'a Vec<usize>>,
+    lines: impl IntoIterator<Item = &'a Vec<usize>>,
+) -> Vec<[usize; 2]> {
+    let closed = faces
+        .into_iter()
+        .flat_map(|face| face.iter().zip(face.iter().cycle().skip(1)));
+    let open = lines
+        .into_iter()
+        .flat_map(|line| line.iter().zip(line.iter().skip(1)));
+    let mut seen = HashSet::new();
+    closed
+        .chain(open)
+        .map(|(a, b)| [*a, *b])
+        .filter(|[a, b]| a != b && seen.insert([*a.min(b), *a.max(b)]))
+        .collect()
+}
+
+/// Edges that are not a side of any face, which formats built around faces have to write
+/// separately
+fn loose_edges(edges: &[[usize; 2]], faces: &[Vec<usize>]) -> Vec<[usize; 2]> {
+    let sides = self::edges(faces, &[])
+        .into_iter()
+        .map(|[a, b]| [a.min(b), a.max(b)])
+        .collect::<HashSet<_>>();
+    edges
+        .iter()
+        .filter(|[a, b]| !sides.contains(&[*a.min(b), *a.max(b)]))
+        .copied()
+        .collect()
+}
+
+/// Lines of a text file with their numbers counting from 1, skipping blank lines and
+/// stripping `#` comments
+fn content_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split('#').next().unwrap_or("").trim()))
+        .filter(|(_, line)| !line.is_empty())
+}
+
+fn number<T: std::str::FromStr>(token: &str, line: usize) -> Result<T, Error> {
+    token.parse().map_err(|_| Error::Syntax {
+        line,
+        message: format!("expected a number, found {token:?}"),
+    })
+}
+
+/// Items separated by spaces
+fn join<T: ToString>(items: impl IntoIterator<Item = T>) -> String {
+    items
+        .into_iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::path::Path;
+
+    use super::{load, native, obj, off, save, Error, Model};
+    use crate::{
+        colour::Colour,
+        mesh::{fourd::Mesh as Mesh4d, Mesh},
+        shape::{fourd::Shape as Shape4d, Shape},
+    };
+
+    /// Rounded vertices, sorted edges, faces and cells
+    type Summary = (
+        Vec<Vec<i64>>,
+        Vec<[usize; 2]>,
+        Vec<Vec<usize>>,
+        Vec<Vec<usize>>,
+    );
+
+    /// Everything about a mesh, with coordinates rounded and edges in a fixed order, so meshes
+    /// that only differ by rounding compare equal
+    fn summary(model: &Model) -> Summary {
+        let round = |coords: &[f128]| coords.iter().map(|x| (x * 1e9).round() as i64).collect();
+        let (vertices, edges, faces, cells) = match model {
+            Model::Mesh(mesh) => (
+                mesh.vertices
+                    .iter()
+                    .map(|point| round(&point[..]))
+                    .collect(),
+                &mesh.edges,
+                &mesh.faces,
+                vec![],
+            ),
+            Model::Mesh4d(mesh) => (
+                mesh.vertices
+                    .iter()
+                    .map(|point| round(&point[..]))
+                    .collect(),
+                &mesh.edges,
+                &mesh.faces,
+                mesh.cells.clone(),
+            ),
+        };
+        let mut edges = edges
+            .iter()
+            .map(|[a, b]| [*a.min(b), *a.max(b)])
+            .collect::<Vec<_>>();
+        edges.sort();
+        (vertices, edges, faces.clone(), cells)
+    }
+
+    fn round_trip(
+        model: &Model,
+        write: fn(&Model, &mut Vec<u8>) -> std::io::Result<()>,
+        parse: fn(&str) -> Result<Model, Error>,
+    ) {
+        let mut bytes = vec![];
+        write(model, &mut bytes).unwrap();
+        let parsed = parse(&String::from_utf8(bytes).unwrap()).unwrap();
+        assert_eq!(summary(&parsed), summary(model));
+    }
+
+    fn write_obj(model: &Model, out: &mut Vec<u8>) -> std::io::Result<()> {
+        let Model::Mesh(mesh) = model else {
+            unreachable!()
+        };
+        obj::write(mesh, out)
+    }
+
+    fn parse_obj(text: &str) -> Result<Model, Error> {
+        obj::parse(text).map(Model::Mesh)
+    }
+
+    #[test]
+    fn round_trips() {
+        let cube = Model::Mesh(Mesh::convex_hull(&Shape::cube().vertices()).unwrap());
+        // Wireframes have no faces, so their edges are written separately
+        let torus = Model::Mesh(Mesh::from(Shape::torus(8, 4).rotate_x_theta(0.3)));
+        let hypercube =
+            Model::Mesh4d(Mesh4d::convex_hull(&Shape4d::hypercube().vertices()).unwrap());
+        let hopf = Model::Mesh4d(Mesh4d::from(Shape4d::hopf_fibration(2, 3, 8)));
+        for model in [&cube, &torus] {
+            round_trip(model, write_obj, parse_obj);
+        }
+        for model in [&cube, &torus, &hypercube, &hopf] {
+            round_trip(model, off::write, off::parse);
+            round_trip(model, native::write, native::parse);
+        }
+    }
+
+    #[test]
+    fn native_metadata() {
+        let mut mesh = Mesh::from(Shape::cube());
+        mesh.glyph = '#';
+        mesh.colour = Some(Colour([255, 136, 0]));
+        let mut bytes = vec![];
+        native::write(&Model::Mesh(mesh), &mut bytes).unwrap();
+        let Ok(Model::Mesh(parsed)) = native::parse(&String::from_utf8(bytes).unwrap()) else {
+            panic!()
+        };
+        assert_eq!(
+            (parsed.glyph, parsed.colour),
+            ('#', Some(Colour([255, 136, 0])))
+        );
+
+        assert!(matches!(
+            native::parse("OFF\n"),
+            Err(Error::Syntax { line: 1, .. })
+        ));
+        assert!(matches!(
+            native::parse("cubers 1\nv 0 0 0\ne 0 1\n"),
+            Err(Error::Index { line: 3, index: 1 })
+        ));
+        assert!(matches!(
+            native::parse("cubers 1\ndimension 4\nv 0 0 0\n"),
+            Err(Error::Syntax { line: 3, .. })
+        ));
+
+        // Glyphs that could not be read back are not written either
+        for glyph in [' ', '\t', '\u{7}'] {
+            let mesh = Mesh { glyph, ..Mesh::from(Shape::cube()) };
+            assert!(native::write(&Model::Mesh(mesh), &mut vec![]).is_err());
+            assert!(native::parse(&format!("cubers 1\nglyph {glyph}\n")).is_err());
+        }
+    }
+
+    #[test]
+    fn files() {
+        let dir = std::env::temp_dir().join(format!("cubers-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let hypercube = Model::Mesh4d(Mesh4d::from(Shape4d::hypercube()));
+        for name in ["hypercube.cubers", "hypercube.4off"] {
+            save(&dir.join(name), &hypercube).unwrap();
+            assert_eq!(
+                summary(&load(&dir.join(name)).unwrap()),
+                summary(&hypercube)
+            );
+        }
+        assert!(matches!(
+            save(&dir.join("hypercube.obj"), &hypercube),
+            Err(Error::NotFourDimensional(_))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unknown_format() {
+        assert!(matches!(
+            load(Path::new("model.blend")),
+            Err(Error::UnknownFormat(_))
+        ));
+        assert!(matches!(
+            load(Path::new("/nonexistent/model.obj")),
+            Err(Error::Io(_))
+        ));
+    }
+}
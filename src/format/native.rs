@@ -0,0 +1,187 @@
+use std::io::{self, Write};
+
+use super::{join, number, Error, Model};
+use crate::{
+    colour::Colour,
+    mesh::{fourd::Mesh as Mesh4d, is_glyph, Mesh},
+    point::{fourd::Point as Point4d, Point},
+};
+
+/// The first line of every native file
+const MAGIC: &str = "cubers 1";
+
+/// Writes a mesh in the native `.cubers` format, which keeps everything a mesh holds:
+///
+/// ```text
+/// cubers 1
+/// dimension 3
+/// glyph *
+/// colour #ff8800
+/// v 1 -1 0.5
+/// e 0 1
+/// f 0 1 2 3
+/// c 0 1 2 3 4 5
+/// ```
+///
+/// After the first line come the dimension, 3 or 4, the character lines are drawn with and an
+/// optional colour. Then each `v` line is a vertex, with one coordinate per dimension, each `e`
+/// an edge between two vertices counting from 0, each `f` a face as the vertices around it in
+/// order, and for 4d meshes each `c` a cell as the faces around it. Indices only refer to
+/// earlier lines. Blank lines and lines starting with `#` are skipped, but comments cannot
+/// follow anything else on a line, since `#` is also a glyph and starts a colour. A glyph that
+/// could not be read back, such as a space, is not written
+pub fn write(model: &Model, out: &mut impl Write) -> io::Result<()> {
+    let (dimension, glyph, colour) = match model {
+        Model::Mesh(mesh) => (3, mesh.glyph, mesh.colour),
+        Model::Mesh4d(mesh) => (4, mesh.glyph, mesh.colour),
+    };
+    if !is_glyph(glyph) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{glyph:?} cannot be a glyph"),
+        ));
+    }
+    writeln!(out, "{MAGIC}")?;
+    writeln!(out, "dimension {dimension}")?;
+    writeln!(out, "glyph {glyph}")?;
+    if let Some(colour) = colour {
+        writeln!(out, "colour {colour}")?;
+    }
+    let (vertices, edges, faces, cells) = match model {
+        Model::Mesh(mesh) => (
+            mesh.vertices
+                .iter()
+                .map(|point| point.to_vec())
+                .collect::<Vec<_>>(),
+            &mesh.edges,
+            &mesh.faces,
+            &vec![],
+        ),
+        Model::Mesh4d(mesh) => (
+            mesh.vertices.iter().map(|point| point.to_vec()).collect(),
+            &mesh.edges,
+            &mesh.faces,
+            &mesh.cells,
+        ),
+    };
+    for vertex in vertices {
+        writeln!(out, "v {}", join(vertex.iter().map(|x| *x as f64)))?;
+    }
+    for edge in edges {
+        writeln!(out, "e {}", join(edge))?;
+    }
+    for face in faces {
+        writeln!(out, "f {}", join(face))?;
+    }
+    for cell in cells {
+        writeln!(out, "c {}", join(cell))?;
+    }
+    Ok(())
+}
+
+/// Reads a mesh written by `write`
+pub fn parse(text: &str) -> Result<Model, Error> {
+    let syntax = |line: usize, message: &str| Error::Syntax {
+        line,
+        message: message.to_string(),
+    };
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    match lines.next() {
+        Some((_, MAGIC)) => (),
+        Some((line, _)) => return Err(syntax(line, &format!("expected {MAGIC:?}"))),
+        None => return Err(syntax(1, "the file is empty")),
+    }
+
+    let mut dimension = 3;
+    let mut glyph = '*';
+    let mut colour = None;
+    let mut vertices = vec![];
+    let mut edges = vec![];
+    let mut faces = vec![];
+    let mut cells = vec![];
+    for (line, content) in lines {
+        let (keyword, rest) = content.split_once(' ').unwrap_or((content, ""));
+        let indices = |limit: usize| {
+            rest.split_whitespace()
+                .map(|token| {
+                    let index = number::<i64>(token, line)?;
+                    usize::try_from(index)
+                        .ok()
+                        .filter(|i| *i < limit)
+                        .ok_or(Error::Index { line, index })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        match keyword {
+            "dimension" if vertices.is_empty() => {
+                dimension = match rest.trim() {
+                    "3" => 3,
+                    "4" => 4,
+                    _ => return Err(syntax(line, "the dimension must be 3 or 4")),
+                }
+            }
+            "glyph" => {
+                let mut chars = rest.chars();
+                glyph = match (chars.next(), chars.next()) {
+                    (Some(char), None) if is_glyph(char) => char,
+                    _ => return Err(syntax(line, "the glyph must be a single visible character")),
+                }
+            }
+            "colour" => {
+                colour = Some(
+                    rest.trim()
+                        .parse::<Colour>()
+                        .map_err(|err| syntax(line, &err.to_string()))?,
+                )
+            }
+            "v" => {
+                let coords = rest
+                    .split_whitespace()
+                    .map(|token| number::<f64>(token, line).map(|x| x as f128))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if coords.len() != dimension {
+                    return Err(syntax(
+                        line,
+                        &format!("a vertex needs {dimension} coordinates"),
+                    ));
+                }
+                vertices.push(coords);
+            }
+            "e" => match indices(vertices.len())?[..] {
+                [a, b] => edges.push([a, b]),
+                _ => return Err(syntax(line, "an edge joins 2 vertices")),
+            },
+            "f" => faces.push(indices(vertices.len())?),
+            "c" if dimension == 4 => cells.push(indices(faces.len())?),
+            _ => return Err(syntax(line, &format!("unexpected {keyword:?}"))),
+        }
+    }
+
+    Ok(match dimension {
+        3 => Model::Mesh(Mesh {
+            vertices: vertices
+                .iter()
+                .map(|coords| Point([coords[0], coords[1], coords[2]]))
+                .collect(),
+            edges,
+            faces,
+            glyph,
+            colour,
+        }),
+        _ => Model::Mesh4d(Mesh4d {
+            vertices: vertices
+                .iter()
+                .map(|coords| Point4d([coords[0], coords[1], coords[2], coords[3]]))
+                .collect(),
+            edges,
+            faces,
+            cells,
+            glyph,
+            colour,
+        }),
+    })
+}
@@ -0,0 +1,126 @@
+use std::io::{self, Write};
+
+use super::{content_lines, edges, join, loose_edges, number, Error};
+use crate::{mesh::Mesh, point::Point};
+
+/// Reads the vertices, faces and polylines of a Wavefront OBJ file. Texture coordinates,
+/// normals, groups and materials are ignored
+pub fn parse(text: &str) -> Result<Mesh, Error> {
+    let mut vertices = vec![];
+    let mut faces = vec![];
+    let mut lines = vec![];
+    for (line, content) in content_lines(text) {
+        let mut tokens = content.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let coords = tokens
+                    .take(3)
+                    .map(|token| number::<f64>(token, line))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let [x, y, z] = coords[..] else {
+                    return Err(Error::Syntax {
+                        line,
+                        message: "a vertex needs 3 coordinates".to_string(),
+                    });
+                };
+                vertices.push(Point([x, y, z].map(|x| x as f128)));
+            }
+            Some(keyword @ ("f" | "l")) => {
+                // Each entry is `v`, `v/vt`, `v//vn` or `v/vt/vn`, counting from 1, or back from
+                // the latest vertex if negative
+                let indices = tokens
+                    .map(|token| {
+                        let index = number::<i64>(token.split('/').next().unwrap_or(""), line)?;
+                        let resolved = match index {
+                            1.. => index - 1,
+                            ..0 => vertices.len() as i64 + index,
+                            0 => -1,
+                        };
+                        usize::try_from(resolved)
+                            .ok()
+                            .filter(|i| *i < vertices.len())
+                            .ok_or(Error::Index { line, index })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if keyword == "f" {
+                    faces.push(indices)
+                } else {
+                    lines.push(indices)
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(Mesh {
+        edges: edges(&faces, &lines),
+        vertices,
+        faces,
+        glyph: '*',
+        colour: None,
+    })
+}
+
+/// Writes the vertices and faces of a mesh as Wavefront OBJ, with edges that are not the side
+/// of a face as lines
+pub fn write(mesh: &Mesh, out: &mut impl Write) -> io::Result<()> {
+    for vertex in &mesh.vertices {
+        writeln!(out, "v {}", join(vertex.map(|x| x as f64)))?;
+    }
+    for face in &mesh.faces {
+        writeln!(out, "f {}", join(face.iter().map(|i| i + 1)))?;
+    }
+    for edge in loose_edges(&mesh.edges, &mesh.faces) {
+        writeln!(out, "l {}", join(edge.map(|i| i + 1)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::parse;
+    use crate::format::Error;
+
+    #[test]
+    fn cube() {
+        let text = "
+            # A unit cube
+            v 0 0 0
+            v 1 0 0
+            v 1 1 0
+            v 0 1 0
+            v 0 0 1
+            v 1 0 1
+            v 1 1 1
+            v 0 1 1
+            vn 0 0 1
+            f 1 4 3 2
+            f 5/1 6/2 7/3 8/4
+            f 1//1 2//1 6//1 5//1
+            f -7 -6 -2 -3
+            f 3 4 8 7
+            f 4 1 5 8
+            l 1 7
+        ";
+        let mesh = parse(text).unwrap();
+        assert_eq!(
+            (mesh.vertices.len(), mesh.edges.len(), mesh.faces.len()),
+            (8, 13, 6)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            parse("v 0 0 0\nf 1 2 1"),
+            Err(Error::Index { line: 2, index: 2 })
+        ));
+        assert!(matches!(
+            parse("v 0 0\n"),
+            Err(Error::Syntax { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse("v 0 0 0\nf 1 x 1"),
+            Err(Error::Syntax { line: 2, .. })
+        ));
+    }
+}
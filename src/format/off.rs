@@ -0,0 +1,277 @@
+use std::io::{self, Write};
+
+use super::{content_lines, edges, join, loose_edges, number, Error, Model};
+use crate::{
+    mesh::{fourd::Mesh as Mesh4d, Mesh},
+    point::{fourd::Point as Point4d, Point},
+};
+
+/// Reads an Object File Format file. `OFF` files hold a 3d mesh of vertices and polygons, and
+/// `4OFF` files a 4d one, followed by cells listing the polygons around them. Polygons with
+/// only two vertices are read as edges. Colours after a face or cell are ignored
+pub fn parse(text: &str) -> Result<Model, Error> {
+    let mut lines = content_lines(text);
+    let syntax = |line: usize, message: &str| Error::Syntax {
+        line,
+        message: message.to_string(),
+    };
+    let (line, header) = lines.next().ok_or_else(|| syntax(1, "the file is empty"))?;
+    let mut tokens = header.split_whitespace();
+    let dimension = match tokens.next() {
+        Some("OFF" | "COFF") => 3,
+        Some("4OFF") => 4,
+        _ => return Err(syntax(line, "expected an OFF or 4OFF header")),
+    };
+
+    // The counts may follow the header on the same line
+    let mut counts = tokens
+        .map(|token| number::<usize>(token, line))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut line = line;
+    if counts.is_empty() {
+        let (next, content) = lines
+            .next()
+            .ok_or_else(|| syntax(line, "missing the vertex and face counts"))?;
+        line = next;
+        counts = content
+            .split_whitespace()
+            .map(|token| number::<usize>(token, line))
+            .collect::<Result<_, _>>()?;
+    }
+    let (vertex_count, face_count, cell_count) = match (dimension, &counts[..]) {
+        (3, [vertices, faces, ..]) => (*vertices, *faces, 0),
+        (4, [vertices, faces, _, cells, ..]) => (*vertices, *faces, *cells),
+        _ => return Err(syntax(line, "missing the vertex and face counts")),
+    };
+
+    let mut next_line = || lines.next().ok_or(Error::Truncated);
+    let mut vertices = vec![];
+    for _ in 0..vertex_count {
+        let (line, content) = next_line()?;
+        let coords = content
+            .split_whitespace()
+            .take(dimension)
+            .map(|token| number::<f64>(token, line).map(|x| x as f128))
+            .collect::<Result<Vec<_>, _>>()?;
+        if coords.len() < dimension {
+            return Err(syntax(
+                line,
+                &format!("a vertex needs {dimension} coordinates"),
+            ));
+        }
+        vertices.push(coords);
+    }
+    let polygons = (0..face_count)
+        .map(|_| next_line().and_then(|(line, content)| indices(line, content, vertex_count)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (faces, lines): (Vec<_>, Vec<_>) = polygons
+        .iter()
+        .cloned()
+        .partition(|polygon| polygon.len() > 2);
+    // Where each polygon ended up in `faces`, if it is a face at all
+    let face_index = polygons
+        .iter()
+        .scan(0, |next, polygon| {
+            let index = (polygon.len() > 2).then_some(*next);
+            *next += index.is_some() as usize;
+            Some(index)
+        })
+        .collect::<Vec<_>>();
+    let cells = (0..cell_count)
+        .map(|_| {
+            let (line, content) = next_line()?;
+            let polygons = indices(line, content, face_count)?;
+            Ok(polygons.iter().filter_map(|i| face_index[*i]).collect())
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let edges = edges(&faces, &lines);
+    Ok(match dimension {
+        3 => Model::Mesh(Mesh {
+            vertices: vertices
+                .iter()
+                .map(|coords| Point([coords[0], coords[1], coords[2]]))
+                .collect(),
+            edges,
+            faces,
+            glyph: '*',
+            colour: None,
+        }),
+        _ => Model::Mesh4d(Mesh4d {
+            vertices: vertices
+                .iter()
+                .map(|coords| Point4d([coords[0], coords[1], coords[2], coords[3]]))
+                .collect(),
+            edges,
+            faces,
+            cells,
+            glyph: '*',
+            colour: None,
+        }),
+    })
+}
+
+/// Writes a mesh as OFF, or as 4OFF with its cells if it is 4d. Edges that are not the side of
+/// any face are written as polygons with two vertices after the faces
+pub fn write(model: &Model, out: &mut impl Write) -> io::Result<()> {
+    let (vertices, edges, faces, cells) = match model {
+        Model::Mesh(mesh) => (
+            mesh.vertices
+                .iter()
+                .map(|point| point.to_vec())
+                .collect::<Vec<_>>(),
+            &mesh.edges,
+            &mesh.faces,
+            None,
+        ),
+        Model::Mesh4d(mesh) => (
+            mesh.vertices.iter().map(|point| point.to_vec()).collect(),
+            &mesh.edges,
+            &mesh.faces,
+            Some(&mesh.cells),
+        ),
+    };
+    let lines = loose_edges(edges, faces);
+    let polygons = faces
+        .iter()
+        .cloned()
+        .chain(lines.iter().map(|line| line.to_vec()));
+    match cells {
+        None => writeln!(
+            out,
+            "OFF\n{} {} {}",
+            vertices.len(),
+            faces.len() + lines.len(),
+            edges.len()
+        )?,
+        Some(cells) => writeln!(
+            out,
+            "4OFF\n{} {} {} {}",
+            vertices.len(),
+            faces.len() + lines.len(),
+            edges.len(),
+            cells.len()
+        )?,
+    }
+    for vertex in &vertices {
+        writeln!(out, "{}", join(vertex.iter().map(|x| *x as f64)))?;
+    }
+    for polygon in polygons {
+        writeln!(out, "{} {}", polygon.len(), join(polygon))?;
+    }
+    for cell in cells.into_iter().flatten() {
+        writeln!(out, "{} {}", cell.len(), join(cell))?;
+    }
+    Ok(())
+}
+
+/// A face or cell line: how many indices there are, then the indices, which count from 0
+fn indices(line: usize, content: &str, limit: usize) -> Result<Vec<usize>, Error> {
+    let mut tokens = content.split_whitespace();
+    let count = number::<usize>(tokens.next().unwrap_or(""), line)?;
+    let indices = tokens
+        .take(count)
+        .map(|token| {
+            let index = number::<i64>(token, line)?;
+            usize::try_from(index)
+                .ok()
+                .filter(|i| *i < limit)
+                .ok_or(Error::Index { line, index })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if indices.len() < count {
+        return Err(Error::Syntax {
+            line,
+            message: format!("expected {count} indices"),
+        });
+    }
+    Ok(indices)
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::parse;
+    use crate::format::{Error, Model};
+
+    #[test]
+    fn cube() {
+        let text = "OFF
+            # A cube, with a colour after the last face
+            8 6 12
+            -1 -1 -1
+            1 -1 -1
+            1 1 -1
+            -1 1 -1
+            -1 -1 1
+            1 -1 1
+            1 1 1
+            -1 1 1
+            4 0 3 2 1
+            4 4 5 6 7
+            4 0 1 5 4
+            4 1 2 6 5
+            4 2 3 7 6
+            4 3 0 4 7 255 0 0
+        ";
+        let Ok(Model::Mesh(mesh)) = parse(text) else {
+            panic!()
+        };
+        assert_eq!(
+            (mesh.vertices.len(), mesh.edges.len(), mesh.faces.len()),
+            (8, 12, 6)
+        );
+    }
+
+    #[test]
+    fn fivecell() {
+        let text = "4OFF 5 10 10 5
+            1 0 0 0
+            0 1 0 0
+            0 0 1 0
+            0 0 0 1
+            0.5 0.5 0.5 0.5
+            3 0 1 2
+            3 0 1 3
+            3 0 1 4
+            3 0 2 3
+            3 0 2 4
+            3 0 3 4
+            3 1 2 3
+            3 1 2 4
+            3 1 3 4
+            3 2 3 4
+            4 0 1 3 6
+            4 0 2 4 7
+            4 1 2 5 8
+            4 3 4 5 9
+            4 6 7 8 9
+        ";
+        let Ok(Model::Mesh4d(mesh)) = parse(text) else {
+            panic!()
+        };
+        let counts = (
+            mesh.vertices.len(),
+            mesh.edges.len(),
+            mesh.faces.len(),
+            mesh.cells.len(),
+        );
+        assert_eq!(counts, (5, 10, 10, 5));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(parse("PLY\n"), Err(Error::Syntax { line: 1, .. })));
+        assert!(matches!(
+            parse("OFF\n3 1 0\n0 0 0\n1 0 0\n"),
+            Err(Error::Truncated)
+        ));
+        assert!(matches!(
+            parse("OFF 1 1 0\n0 0 0\n3 0 0 5\n"),
+            Err(Error::Index { line: 3, index: 5 })
+        ));
+        assert!(matches!(
+            parse("OFF 1 1 0\n0 0\n"),
+            Err(Error::Syntax { line: 2, .. })
+        ));
+    }
+}
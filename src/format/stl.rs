@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use super::{content_lines, edges, number, Error};
+use crate::{mesh::Mesh, point::Point};
+
+/// Size of a binary STL header plus its triangle count
+const HEADER: usize = 84;
+/// Size of each binary STL triangle: a normal and 3 vertices of 3 floats, and 2 spare bytes
+const TRIANGLE: usize = 50;
+
+/// Reads an ASCII or binary STL file. Each triangle lists its own copy of its corners, so
+/// corners at the same point are merged to give the edges between neighbouring triangles
+pub fn parse(bytes: &[u8]) -> Result<Mesh, Error> {
+    // Binary files may also start with "solid", so their length is checked first, and text
+    // never contains the zero bytes that pad binary headers
+    let binary_length = bytes
+        .get(80..HEADER)
+        .map(|count| HEADER + TRIANGLE * u32::from_le_bytes(count.try_into().unwrap()) as usize);
+    let is_ascii = binary_length != Some(bytes.len())
+        && bytes.trim_ascii_start().starts_with(b"solid")
+        && !bytes.contains(&0);
+    let triangles = if is_ascii {
+        parse_ascii(&String::from_utf8_lossy(bytes))?
+    } else {
+        parse_binary(bytes)?
+    };
+
+    let mut vertices = vec![];
+    let mut indices = HashMap::new();
+    let faces = triangles
+        .into_iter()
+        .map(|triangle| {
+            triangle
+                .into_iter()
+                .map(|point| {
+                    *indices.entry(point.grid_key()).or_insert_with(|| {
+                        vertices.push(point);
+                        vertices.len() - 1
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    Ok(Mesh {
+        edges: edges(&faces, &[]),
+        vertices,
+        faces,
+        glyph: '*',
+        colour: None,
+    })
+}
+
+fn parse_ascii(text: &str) -> Result<Vec<Vec<Point>>, Error> {
+    let mut facets = vec![];
+    let mut corners = vec![];
+    for (line, content) in content_lines(text) {
+        let mut tokens = content.split_whitespace();
+        match tokens.next() {
+            Some("vertex") => {
+                let coords = tokens
+                    .map(|token| number::<f64>(token, line).map(|x| x as f128))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let [x, y, z] = coords[..] else {
+                    return Err(Error::Syntax {
+                        line,
+                        message: "a vertex needs 3 coordinates".to_string(),
+                    });
+                };
+                corners.push(Point([x, y, z]));
+            }
+            Some("endloop") => {
+                if corners.len() < 3 {
+                    return Err(Error::Syntax {
+                        line,
+                        message: "a facet needs at least 3 vertices".to_string(),
+                    });
+                }
+                facets.push(std::mem::take(&mut corners));
+            }
+            _ => (),
+        }
+    }
+    Ok(facets)
+}
+
+fn parse_binary(bytes: &[u8]) -> Result<Vec<Vec<Point>>, Error> {
+    let count = bytes.get(80..HEADER).ok_or(Error::Truncated)?;
+    let count = u32::from_le_bytes(count.try_into().unwrap()) as usize;
+    let body = bytes
+        .get(HEADER..HEADER + count * TRIANGLE)
+        .ok_or(Error::Truncated)?;
+    let float = |bytes: &[u8]| f32::from_le_bytes(bytes.try_into().unwrap()) as f128;
+    Ok(body
+        .chunks_exact(TRIANGLE)
+        .map(|triangle| {
+            // Skip the normal, which is recalculated wherever it is needed
+            triangle[12..48]
+                .chunks_exact(12)
+                .map(|corner| Point([0, 4, 8].map(|i| float(&corner[i..i + 4]))))
+                .collect()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::parse;
+    use crate::format::Error;
+
+    const TETRAHEDRON: [[[f32; 3]; 3]; 4] = [
+        [[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]],
+        [[0., 0., 0.], [0., 0., 1.], [1., 0., 0.]],
+        [[0., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+        [[1., 0., 0.], [0., 0., 1.], [0., 1., 0.]],
+    ];
+
+    #[test]
+    fn ascii() {
+        let mut text = "solid tetrahedron\n".to_string();
+        for triangle in TETRAHEDRON {
+            text += "facet normal 0 0 0\nouter loop\n";
+            for [x, y, z] in triangle {
+                text += &format!("vertex {x} {y} {z}\n");
+            }
+            text += "endloop\nendfacet\n";
+        }
+        text += "endsolid tetrahedron\n";
+        let mesh = parse(text.as_bytes()).unwrap();
+        assert_eq!(
+            (mesh.vertices.len(), mesh.edges.len(), mesh.faces.len()),
+            (4, 6, 4)
+        );
+    }
+
+    #[test]
+    fn binary() {
+        // A header starting with "solid", as some exporters write
+        let mut bytes = b"solid".to_vec();
+        bytes.resize(80, 0);
+        bytes.extend(4u32.to_le_bytes());
+        for triangle in TETRAHEDRON {
+            bytes.extend([0u8; 12]);
+            bytes.extend(triangle.iter().flatten().flat_map(|x| x.to_le_bytes()));
+            bytes.extend([0u8; 2]);
+        }
+        let mesh = parse(&bytes).unwrap();
+        assert_eq!(
+            (mesh.vertices.len(), mesh.edges.len(), mesh.faces.len()),
+            (4, 6, 4)
+        );
+
+        assert!(matches!(parse(&bytes[..150]), Err(Error::Truncated)));
+        assert!(matches!(
+            parse(b"solid x\nouter loop\nvertex 0 0\n"),
+            Err(Error::Syntax { line: 3, .. })
+        ));
+    }
+}
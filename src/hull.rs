@@ -0,0 +1,452 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::{
+    mesh::{fourd::Mesh as Mesh4d, Mesh},
+    point::{fourd::Point as Point4d, Point},
+    shape::{fourd::Shape as Shape4d, Shape},
+};
+
+/// Relative size of the nudges given to points so they are in general position
+const PERTURBATION: f128 = 1e-12;
+/// Relative distance within which a point counts as lying on a facet's hyperplane
+const TOLERANCE: f128 = 1e-9;
+
+/// The boundary of the convex hull of a set of points in D dimensions. Everything refers to
+/// the points by their index in the input
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Hull {
+    /// Points that are corners of the hull, in input order
+    pub vertices: Vec<usize>,
+    /// The (D - 1)-dimensional faces: polygons in 3d, polyhedral cells in 4d
+    pub facets: Vec<Vec<usize>>,
+    /// The (D - 2)-dimensional faces where two facets meet: edges in 3d, polygons in 4d
+    pub ridges: Vec<Vec<usize>>,
+    pub edges: Vec<[usize; 2]>,
+}
+
+/// A simplex on the boundary of the perturbed hull, with its outward normal
+struct Simplex<const D: usize> {
+    corners: [usize; D],
+    normal: [f128; D],
+    offset: f128,
+}
+
+/// Computes the convex hull of the points. Returns `None` if they all lie in a hyperplane, so
+/// the hull has no interior
+pub fn convex_hull<const D: usize>(points: &[[f128; D]]) -> Option<Hull> {
+    // Repeated points would otherwise look like two corners in the same place
+    let mut seen = HashSet::new();
+    let unique = (0..points.len())
+        .filter(|i| seen.insert(points[*i].map(|x| (x * 1e9).round() as i64)))
+        .collect_vec();
+    let points = unique.iter().map(|i| points[*i]).collect_vec();
+
+    let scale = points
+        .iter()
+        .flatten()
+        .map(|x| x.abs())
+        .fold(0., f128::max)
+        .max(1e-30);
+    let perturbed = perturb(&points, scale);
+    let simplices = simplicial_hull(&perturbed, scale)?;
+
+    // Simplices lying in the same hyperplane of the original points make up one facet. Slivers
+    // whose corners are only independent thanks to the perturbation are skipped; the simplices
+    // around them cover the same facets
+    let mut facets = vec![];
+    let mut seen = HashSet::new();
+    for simplex in &simplices {
+        let corners = simplex.corners.map(|i| points[i]);
+        let Some((normal, offset)) = hyperplane(&corners, PERTURBATION) else {
+            continue;
+        };
+        let facet = (0..points.len())
+            .filter(|i| (dot(&normal, &points[*i]) - offset).abs() < TOLERANCE * scale)
+            .collect_vec();
+        if seen.insert(facet.clone()) {
+            facets.push(facet);
+        }
+    }
+
+    // A point is a corner when the facets containing it meet only at that point, rather than
+    // along an edge or face it lies in the middle of
+    let vertices = facets
+        .iter()
+        .flatten()
+        .copied()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|point| {
+            let mut containing = facets.iter().filter(|facet| facet.contains(point));
+            let first = containing.next().unwrap().clone();
+            let common = containing.fold(first, |common, facet| {
+                common.into_iter().filter(|v| facet.contains(v)).collect()
+            });
+            common.len() == 1
+        })
+        .collect_vec();
+    let facets = facets
+        .into_iter()
+        .map(|facet| facet.into_iter().filter(|i| vertices.contains(i)).collect_vec())
+        .collect_vec();
+    let edges = edges(&facets);
+    let ridges = ridges(&facets, D);
+
+    let original = |i: &usize| unique[*i];
+    Some(Hull {
+        vertices: vertices.iter().map(original).collect(),
+        facets: facets.iter().map(|facet| facet.iter().map(original).collect()).collect(),
+        ridges: ridges.iter().map(|ridge| ridge.iter().map(original).collect()).collect(),
+        edges: edges.iter().map(|edge| edge.each_ref().map(original)).collect(),
+    })
+}
+
+/// Nudges each point by a tiny pseudo-random amount so no D + 1 of them share a hyperplane
+fn perturb<const D: usize>(points: &[[f128; D]], scale: f128) -> Vec<[f128; D]> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state as f128 / u64::MAX as f128) * 2. - 1.
+    };
+    points
+        .iter()
+        .map(|point| point.map(|x| x + random() * PERTURBATION * scale))
+        .collect()
+}
+
+/// Incremental beneath-beyond hull of points in general position, as a list of simplices
+fn simplicial_hull<const D: usize>(points: &[[f128; D]], scale: f128) -> Option<Vec<Simplex<D>>> {
+    let start = initial_simplex(points, scale)?;
+    let inside = centroid(start.iter().map(|i| points[*i]));
+    let make = |corners: [usize; D]| -> Option<Simplex<D>> {
+        // Simplices of perturbed points can be nearly flat, but never as flat as this
+        let (mut normal, mut offset) = hyperplane(&corners.map(|i| points[i]), PERTURBATION.powi(2))?;
+        if dot(&normal, &inside) > offset {
+            normal = normal.map(|x| -x);
+            offset = -offset;
+        }
+        Some(Simplex { corners, normal, offset })
+    };
+
+    let mut simplices = (0..=D)
+        .map(|skip| {
+            let corners = start.iter().enumerate().filter(|(i, _)| *i != skip).map(|(_, c)| *c);
+            make(to_array(corners))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let epsilon = scale * 1e-24;
+    for (index, point) in points.iter().enumerate() {
+        if start.contains(&index) {
+            continue;
+        }
+        let (visible, hidden): (Vec<_>, Vec<_>) = simplices
+            .into_iter()
+            .partition(|simplex| dot(&simplex.normal, point) - simplex.offset > epsilon);
+        simplices = hidden;
+        if visible.is_empty() {
+            continue;
+        }
+        // Ridges belonging to exactly one visible simplex form the horizon, which the new point
+        // is joined to
+        let mut horizon: HashMap<Vec<usize>, usize> = HashMap::new();
+        for simplex in &visible {
+            for skip in 0..D {
+                let ridge = simplex
+                    .corners
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != skip)
+                    .map(|(_, c)| *c)
+                    .sorted()
+                    .collect_vec();
+                *horizon.entry(ridge).or_default() += 1;
+            }
+        }
+        for (ridge, count) in horizon {
+            if count == 1 {
+                simplices.extend(make(to_array(ridge.into_iter().chain([index]))));
+            }
+        }
+    }
+    Some(simplices)
+}
+
+/// D + 1 points spanning all D dimensions, picked greedily to be as spread out as possible
+fn initial_simplex<const D: usize>(points: &[[f128; D]], scale: f128) -> Option<Vec<usize>> {
+    let mut chosen = vec![0];
+    // Orthonormal basis of the directions spanned so far
+    let mut basis: Vec<[f128; D]> = vec![];
+    let origin = *points.first()?;
+    for _ in 0..D {
+        let residual = |point: &[f128; D]| {
+            let mut v: [f128; D] = std::array::from_fn(|i| point[i] - origin[i]);
+            for b in &basis {
+                let along = dot(&v, b);
+                v = std::array::from_fn(|i| v[i] - along * b[i]);
+            }
+            v
+        };
+        let (index, v) = points
+            .iter()
+            .enumerate()
+            .map(|(i, point)| (i, residual(point)))
+            .max_by(|(_, a), (_, b)| dot(a, a).total_cmp(&dot(b, b)))?;
+        let length = dot(&v, &v).sqrt();
+        if length < TOLERANCE * scale {
+            return None;
+        }
+        basis.push(v.map(|x| x / length));
+        chosen.push(index);
+    }
+    Some(chosen)
+}
+
+/// Unit normal and offset of the hyperplane through D points, or `None` if they are so close
+/// to lying in a lower dimensional plane that the volume they span, relative to the size of
+/// the simplex, is below `flatness`
+fn hyperplane<const D: usize>(corners: &[[f128; D]; D], flatness: f128) -> Option<([f128; D], f128)> {
+    // The normal is the generalised cross product of the edges from the first corner, whose
+    // components are the signed minors of the matrix of edges
+    let edges = corners[1..]
+        .iter()
+        .map(|corner| std::array::from_fn::<f128, D, _>(|i| corner[i] - corners[0][i]))
+        .collect_vec();
+    let normal: [f128; D] = std::array::from_fn(|column| {
+        let minor = edges
+            .iter()
+            .map(|edge| (0..D).filter(|c| *c != column).map(|c| edge[c]).collect_vec())
+            .collect_vec();
+        let sign = if column % 2 == 0 { 1. } else { -1. };
+        sign * determinant(minor)
+    });
+    let length = dot(&normal, &normal).sqrt();
+    let size = edges.iter().map(|edge| dot(edge, edge).sqrt()).fold(0., f128::max);
+    if length <= size.powi(D as i32 - 1) * flatness {
+        return None;
+    }
+    let normal = normal.map(|x| x / length);
+    Some((normal, dot(&normal, &corners[0])))
+}
+
+fn determinant(mut matrix: Vec<Vec<f128>>) -> f128 {
+    let n = matrix.len();
+    let mut det = 1.;
+    for col in 0..n {
+        let pivot = (col..n).max_by(|a, b| matrix[*a][col].abs().total_cmp(&matrix[*b][col].abs()));
+        let Some(pivot) = pivot.filter(|p| matrix[*p][col] != 0.) else {
+            return 0.;
+        };
+        if pivot != col {
+            matrix.swap(pivot, col);
+            det = -det;
+        }
+        det *= matrix[col][col];
+        let pivot_row = matrix[col].clone();
+        for row in matrix.iter_mut().skip(col + 1) {
+            let factor = row[col] / pivot_row[col];
+            row.iter_mut().zip(&pivot_row).skip(col).for_each(|(x, p)| *x -= factor * p);
+        }
+    }
+    det
+}
+
+/// A pair of vertices is an edge when the only vertices shared by every facet containing
+/// both are the pair themselves
+fn edges(facets: &[Vec<usize>]) -> Vec<[usize; 2]> {
+    let mut shared: HashMap<[usize; 2], BTreeSet<usize>> = HashMap::new();
+    for facet in facets {
+        let members = facet.iter().copied().collect::<BTreeSet<_>>();
+        for (a, b) in facet.iter().tuple_combinations() {
+            shared
+                .entry([*a.min(b), *a.max(b)])
+                .and_modify(|common| common.retain(|v| members.contains(v)))
+                .or_insert_with(|| members.clone());
+        }
+    }
+    shared
+        .into_iter()
+        .filter(|(_, common)| common.len() == 2)
+        .map(|(pair, _)| pair)
+        .sorted()
+        .collect()
+}
+
+/// Ridges are the faces where two facets meet. Below 5d, any two facets sharing at least
+/// D - 1 vertices meet in a ridge rather than a smaller face
+fn ridges(facets: &[Vec<usize>], dimension: usize) -> Vec<Vec<usize>> {
+    facets
+        .iter()
+        .tuple_combinations()
+        .map(|(a, b)| a.iter().filter(|v| b.contains(v)).copied().collect_vec())
+        .filter(|common| common.len() >= dimension - 1)
+        .collect()
+}
+
+/// Orders the vertices of a polygon so consecutive ones, and the last and first, share an edge
+pub fn order_polygon(polygon: &[usize], edges: &[[usize; 2]]) -> Vec<usize> {
+    let Some(&first) = polygon.first() else {
+        return vec![];
+    };
+    let mut ordered = vec![first];
+    while ordered.len() < polygon.len() {
+        let last = *ordered.last().unwrap();
+        let next = edges.iter().find_map(|[a, b]| {
+            let other = if *a == last { *b } else if *b == last { *a } else { return None };
+            (polygon.contains(&other) && !ordered.contains(&other)).then_some(other)
+        });
+        match next {
+            Some(next) => ordered.push(next),
+            None => break,
+        }
+    }
+    ordered
+}
+
+fn dot<const D: usize>(a: &[f128; D], b: &[f128; D]) -> f128 {
+    (0..D).map(|i| a[i] * b[i]).sum()
+}
+
+fn centroid<const D: usize>(points: impl Iterator<Item = [f128; D]>) -> [f128; D] {
+    let (sum, count) = points.fold(([0.; D], 0), |(sum, count), point| {
+        (std::array::from_fn(|i| sum[i] + point[i]), count + 1)
+    });
+    sum.map(|x| x / count as f128)
+}
+
+fn to_array<const D: usize>(items: impl IntoIterator<Item = usize>) -> [usize; D] {
+    let items = items.into_iter().collect_vec();
+    std::array::from_fn(|i| items[i])
+}
+
+impl Mesh {
+    /// The convex hull of the points as a mesh, with a face for every facet
+    pub fn convex_hull(points: &[Point]) -> Option<Mesh> {
+        let hull = convex_hull(&points.iter().map(|point| point.0).collect_vec())?;
+        let index = hull
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(new, old)| (*old, new))
+            .collect::<HashMap<_, _>>();
+        Some(Mesh {
+            vertices: hull.vertices.iter().map(|i| points[*i]).collect(),
+            edges: hull.edges.iter().map(|edge| edge.map(|i| index[&i])).collect(),
+            faces: hull
+                .facets
+                .iter()
+                .map(|facet| order_polygon(facet, &hull.edges).iter().map(|i| index[i]).collect())
+                .collect(),
+            glyph: '*',
+            colour: None,
+        })
+    }
+}
+
+impl Mesh4d {
+    /// The convex hull of the points as a mesh, with a face for every ridge and a cell for
+    /// every facet
+    pub fn convex_hull(points: &[Point4d]) -> Option<Mesh4d> {
+        let hull = convex_hull(&points.iter().map(|point| point.0).collect_vec())?;
+        let index = hull
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(new, old)| (*old, new))
+            .collect::<HashMap<_, _>>();
+        let cells = hull
+            .facets
+            .iter()
+            .map(|facet| {
+                (0..hull.ridges.len())
+                    .filter(|ridge| hull.ridges[*ridge].iter().all(|v| facet.contains(v)))
+                    .collect()
+            })
+            .collect();
+        Some(Mesh4d {
+            vertices: hull.vertices.iter().map(|i| points[*i]).collect(),
+            edges: hull.edges.iter().map(|edge| edge.map(|i| index[&i])).collect(),
+            faces: hull
+                .ridges
+                .iter()
+                .map(|ridge| order_polygon(ridge, &hull.edges).iter().map(|i| index[i]).collect())
+                .collect(),
+            cells,
+            glyph: '*',
+            colour: None,
+        })
+    }
+}
+
+impl Shape {
+    /// The edges of the convex hull of the points
+    pub fn convex_hull(points: &[Point]) -> Option<Shape> {
+        Mesh::convex_hull(points).map(|mesh| Shape::from(&mesh))
+    }
+}
+
+impl Shape4d {
+    /// The edges of the convex hull of the points
+    pub fn convex_hull(points: &[Point4d]) -> Option<Shape4d> {
+        Mesh4d::convex_hull(points).map(|mesh| Shape4d::from(&mesh))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::convex_hull;
+    use crate::{
+        mesh::fourd::Mesh as Mesh4d,
+        shape::{fourd::Shape as Shape4d, Shape},
+    };
+
+    fn counts<const D: usize>(points: &[[f128; D]]) -> (usize, usize, usize, usize) {
+        let hull = convex_hull(points).unwrap();
+        (hull.vertices.len(), hull.edges.len(), hull.ridges.len(), hull.facets.len())
+    }
+
+    #[test]
+    fn cube() {
+        let mut points = Shape::cube().vertices().iter().map(|point| point.0).collect::<Vec<_>>();
+        // Points inside, in the middle of a face or edge, or repeated, are not extra corners
+        points.push([0.1, 0.2, 0.3]);
+        points.push([0., 0., 1.]);
+        points.push([1., 1., 0.]);
+        points.push([1., 1., 1.]);
+        assert_eq!(counts(&points), (8, 12, 12, 6));
+    }
+
+    #[test]
+    fn polytopes() {
+        let points = |shape: Shape4d| shape.vertices().iter().map(|point| point.0).collect::<Vec<_>>();
+        assert_eq!(counts(&points(Shape4d::fivecell())), (5, 10, 10, 5));
+        assert_eq!(counts(&points(Shape4d::hypercube())), (16, 32, 24, 8));
+        assert_eq!(counts(&points(Shape4d::sixteencell())), (8, 24, 32, 16));
+        assert_eq!(counts(&points(Shape4d::twentyfourcell())), (24, 96, 96, 24));
+    }
+
+    #[test]
+    fn flat() {
+        assert!(convex_hull(&[[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [1., 1., 0.]]).is_none());
+    }
+
+    #[test]
+    fn mesh_faces() {
+        let vertices = Shape4d::hypercube().vertices();
+        let mesh = Mesh4d::convex_hull(&vertices).unwrap();
+        assert_eq!(mesh.cells.len(), 8);
+        assert!(mesh.cells.iter().all(|cell| cell.len() == 6));
+        // Faces are squares with their corners in order around the edge
+        for face in &mesh.faces {
+            assert_eq!(face.len(), 4);
+            for (a, b) in face.iter().zip(face.iter().cycle().skip(1)) {
+                let side = (mesh.vertices[*a] - mesh.vertices[*b]).l2_norm();
+                assert!((side - 2.).abs() < 1e-20);
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+#![feature(f128, mpmc_channel)]
+
+pub mod camera;
+pub mod cast;
+pub mod colour;
+pub mod hull;
+pub mod config;
+pub mod format;
+pub mod matrix;
+pub mod mesh;
+pub mod point;
+pub mod rotation;
+pub mod scene;
+pub mod script;
+pub mod line;
+pub mod screen;
+pub mod shape;
+pub mod timeline;
+pub mod wythoff;
+
+#[cfg(test)]
+pub mod test {
+    use std::f128;
+    use crate::{
+        camera::Camera,
+        line::{fourd::Line as Line4d, Line},
+        matrix::Matrix3x3,
+        point::{fourd::Point as Point4d, Point},
+    };
+
+    #[test]
+    fn point() {
+        let point = Point([3., 4., 0.]);
+        assert_eq!(point.l2_norm(), 5.);
+        let point = Point([1., 2., 2.]);
+        assert_eq!(point.l2_norm(), 3.);
+    }
+
+    #[test]
+    fn point_4d() {
+        // The w coordinate counts towards a 4d point's length like the others
+        let point = Point4d([1., 1., 1., 1.]);
+        assert_eq!(point.l1_norm(), 4.);
+        assert_eq!(point.l2_norm(), 2.);
+        let point = Point4d([0., 0., 0., -3.]);
+        assert_eq!(point.l1_norm(), 3.);
+        assert_eq!(point.l2_norm(), 3.);
+        assert_eq!(point.normalize().0, [0., 0., 0., -1.]);
+    }
+
+    #[test]
+    fn matmul() {
+        let matrix0: Matrix3x3 = Matrix3x3([[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]]);
+        let matrix1: Matrix3x3 = Matrix3x3([[1., 2., 3.], [3., 1., 2.], [2., 3., 1.]]);
+
+        let swap23:  Matrix3x3 = Matrix3x3([[1., 0., 0.], [0., 0., 1.], [0., 1., 0.]]);
+
+        let matrix2: Matrix3x3 = Matrix3x3([[1., 2., 3.], [2., 3., 1.], [3., 1., 2.]]);
+        let matrix3: Matrix3x3 = Matrix3x3([[1., 3., 2.], [2., 1., 3.], [3., 2., 1.]]);
+        let matrix4: Matrix3x3 = Matrix3x3([[1., 3., 2.], [3., 2., 1.], [2., 1., 3.]]);
+
+        assert_eq!(matrix1 * matrix0, matrix1);
+        assert_eq!(matrix0 * matrix1, matrix1);
+        assert_eq!(swap23 * matrix1, matrix2);
+
+        assert_eq!(matrix1 * swap23, matrix4);
+
+        assert_eq!(matrix2 * swap23, matrix3);
+    }
+
+    #[test]
+    fn point_matmul() {
+        let pi = f128::consts::PI;
+        let point = Point([1., 1., 0.]);
+        let xrotate = Matrix3x3::rotate_x_theta(pi/2.);
+        println!("{}", point);
+        println!("{}", xrotate * point);
+    }
+
+    #[test]
+    fn clip_behind_camera() {
+        let camera = Camera::default();
+        let view_projection = camera.view_projection(1.);
+
+        let behind = Line([Point([0., -5., 0.]), Point([1., -6., 1.])]);
+        assert!(Camera::clip_line(&view_projection, behind).is_none());
+
+        let through = Line([Point([0., -10., 0.]), Point([0., 10., 0.])]);
+        let clipped = Camera::clip_line(&view_projection, through).unwrap();
+        assert!(clipped.iter().all(|point| point[3] > 0.));
+        assert!((clipped[0][3] - camera.near).abs() < 1e-9);
+
+        let through_eye = Line4d([Point4d([0., 0., 0., -10.]), Point4d([0., 0., 0., 1.])]);
+        let projected = through_eye.to_line_3d().unwrap();
+        assert!(projected.iter().all(|point| point.iter().all(|x| x.is_finite())));
+        assert!(Line4d([Point4d([1., 0., 0., -5.]), Point4d([0., 1., 0., -4.])]).to_line_3d().is_none());
+    }
+}
@@ -30,9 +30,41 @@ impl Mul<f128> for Line {
 }
 
 impl Line {
-    /// Turns 4d line into 3d line
-    pub fn to_line_3d(&self) -> Line3d {
-        Line3d(self.map(|point| Point3d(point.to_cube_xyz().into())))
+    /// Turns 4d line into 3d line, or `None` if the line lies entirely behind the eye
+    pub fn to_line_3d(&self) -> Option<Line3d> {
+        let line = self.clip_to_eye()?;
+        Some(Line3d(line.map(|point| Point3d(point.to_cube_xyz().into()))))
+    }
+
+    /// Clips the line to the part in front of the eye hyperplane
+    pub fn clip_to_eye(&self) -> Option<Line> {
+        self.clip(Point::eye_clip_distance)
+    }
+
+    /// Clips the line to the half-space where `distance` is non-negative. `distance` must be
+    /// an affine function of the point, such as a signed distance to a hyperplane
+    pub fn clip(&self, distance: impl Fn(&Point) -> f128) -> Option<Line> {
+        let (start, end) = (distance(&self[0]), distance(&self[1]));
+        match (start >= 0., end >= 0.) {
+            (true, true) => Some(*self),
+            (false, false) => None,
+            (start_inside, _) => {
+                let t = start / (start - end);
+                let crossing = self[0] + (self[1] - self[0]) * t;
+                if start_inside {
+                    Some(Line([self[0], crossing]))
+                } else {
+                    Some(Line([crossing, self[1]]))
+                }
+            }
+        }
+    }
+
+    pub fn find_78_points_in_between(&self) -> Vec<Point> {
+        let delta = self[1] - self[0];
+        (0..=78)
+            .map(|num| self[0] + delta * (num as f128 / 78.))
+            .collect()
     }
 
     /// Rotate line around the zw plane theta radians
@@ -1,40 +1,197 @@
-#![feature(f128, mpmc_channel)]
+#![feature(f128)]
 
-use std::{io::{self, Write}, process::exit};
+use std::{env, fmt::Display, fs::File, io::{self, BufWriter, Write}, path::{Path, PathBuf}, process::exit};
 
-use screen::Screen;
-pub mod matrix;
-pub mod point;
-pub mod line;
-pub mod screen;
-pub mod shape;
+use cubers::{
+    cast::Recorder,
+    config::Config,
+    format,
+    mesh::Model,
+    scene::{self, Object, Scene},
+    script::Script,
+    screen::{raster::{self, Image}, svg::SvgOptions, Screen, FRAME_TIME},
+    shape::{fourd::Shape as Shape4d, Shape},
+    wythoff::{Diagram, Polytope},
+};
+
+/// Something that can be picked from the menu or given on the command line
+enum Mode {
+    Shape(fn() -> Shape),
+    Shape4d(fn() -> Shape4d),
+    ManualHypercube,
+    Calibrate,
+    Slice(fn() -> Shape4d),
+    Scene(fn() -> Scene),
+    SceneFile(Box<Scene>),
+    Script(fn() -> Script),
+    ScriptFile(PathBuf),
+    Polytope(Polytope),
+    Model(Model),
+}
+
+const USAGE: &str = "usage: cubers [--wythoff <schläfli symbol or diagram, e.g. {3,3,5} or x3o3x5o> | --file <.cubers, .obj, .off, .4off or .stl file> | --scene <.toml file> | --script <.rhai file>] [--save <.cubers, .obj, .off or .4off file> | --record <.cast file> [--frames <count>] | --svg <.svg file> | --image <.png or .ppm file> [--width <pixels>] | --gif <.gif file> [--frames <count>] [--width <pixels>] | --text <directory, or - for stdout> [--frames <count>]]";
+
+/// How many frames `--record` writes unless told otherwise, 6 seconds at 20 frames a second
+const RECORD_FRAMES: usize = 120;
+
+/// Width of `--image` and `--gif` pictures unless told otherwise
+const IMAGE_WIDTH: usize = 640;
+
+/// What was asked for on the command line
+#[derive(Default)]
+struct Args {
+    /// The shape to show, or `None` to ask for one
+    mode: Option<Mode>,
+    /// Where to record an asciinema cast instead of drawing to the terminal
+    record: Option<String>,
+    frames: Option<usize>,
+    /// Where to draw the first frame as an SVG image instead
+    svg: Option<String>,
+    /// Where to draw the first frame as a PNG or PPM image instead
+    image: Option<String>,
+    /// Where to draw a whole turn of the shape as an animated GIF instead
+    gif: Option<String>,
+    width: Option<usize>,
+    /// Where to write frames as plain text instead, a directory or `-` for stdout
+    text: Option<String>,
+}
+
+fn fail(message: &dyn Display) -> ! {
+    eprintln!("{message}");
+    exit(1);
+}
+
+/// Reads the command line. With `--save`, writes the shape to a file and exits instead
+fn parse_args() -> Args {
+    let mut parsed = Args::default();
+    let mut save = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--wythoff", Some(diagram)) => {
+                let polytope = diagram
+                    .parse::<Diagram>()
+                    .and_then(|diagram| diagram.build())
+                    .unwrap_or_else(|err| fail(&err));
+                parsed.mode = Some(Mode::Polytope(polytope));
+            }
+            ("--file", Some(path)) => {
+                let model = format::load(Path::new(&path))
+                    .unwrap_or_else(|err| fail(&format!("{path}: {err}")));
+                parsed.mode = Some(Mode::Model(model));
+            }
+            ("--scene", Some(path)) => {
+                let scene = scene::file::load(Path::new(&path))
+                    .unwrap_or_else(|err| fail(&format!("{path}: {err}")));
+                parsed.mode = Some(Mode::SceneFile(Box::new(scene)));
+            }
+            ("--script", Some(path)) => {
+                // Loaded again when it runs, but any mistakes are reported now
+                if let Err(err) = Script::load(Path::new(&path)) {
+                    fail(&format!("{path}: {err}"));
+                }
+                parsed.mode = Some(Mode::ScriptFile(PathBuf::from(path)));
+            }
+            ("--save", Some(path)) => save = Some(path),
+            ("--record", Some(path)) => parsed.record = Some(path),
+            ("--svg", Some(path)) => parsed.svg = Some(path),
+            ("--image", Some(path)) => parsed.image = Some(path),
+            ("--gif", Some(path)) => parsed.gif = Some(path),
+            ("--text", Some(path)) => parsed.text = Some(path),
+            ("--width", Some(width)) => {
+                parsed.width = Some(width.parse().unwrap_or_else(|_| fail(&USAGE)))
+            }
+            ("--frames", Some(count)) => {
+                parsed.frames = Some(count.parse().unwrap_or_else(|_| fail(&USAGE)))
+            }
+            _ => fail(&USAGE),
+        }
+    }
+
+    if let Some(path) = save {
+        let model = match parsed.mode {
+            Some(Mode::Polytope(polytope)) => Model::from(polytope),
+            Some(Mode::Model(model)) => model,
+            _ => fail(&"--save needs a shape from --wythoff or --file"),
+        };
+        if let Err(err) = format::save(Path::new(&path), &model) {
+            fail(&format!("{path}: {err}"));
+        }
+        exit(0);
+    }
+    parsed
+}
+
+const MODES: &[(&str, Mode)] = &[
+    ("cube", Mode::Shape(Shape::cube)),
+    ("fivecell", Mode::Shape4d(Shape4d::fivecell)),
+    ("hypercube", Mode::Shape4d(Shape4d::hypercube)),
+    ("controllable hypercube (hjklin rotate, arrows orbit, +/- zoom, o toggles orthographic)", Mode::ManualHypercube),
+    ("calibrate the character aspect ratio", Mode::Calibrate),
+    ("sixteen-cell", Mode::Shape4d(Shape4d::sixteencell)),
+    ("twenty-four-cell", Mode::Shape4d(Shape4d::twentyfourcell)),
+    ("hundred-twenty-cell", Mode::Shape4d(Shape4d::hundredtwentycell)),
+    ("six-hundred-cell", Mode::Shape4d(Shape4d::sixhundredcell)),
+    ("tetrahedron", Mode::Shape(Shape::tetrahedron)),
+    ("octahedron", Mode::Shape(Shape::octahedron)),
+    ("dodecahedron", Mode::Shape(Shape::dodecahedron)),
+    ("icosahedron", Mode::Shape(Shape::icosahedron)),
+    ("truncated tetrahedron", Mode::Shape(Shape::truncated_tetrahedron)),
+    ("cuboctahedron", Mode::Shape(Shape::cuboctahedron)),
+    ("truncated cube", Mode::Shape(Shape::truncated_cube)),
+    ("truncated octahedron", Mode::Shape(Shape::truncated_octahedron)),
+    ("rhombicuboctahedron", Mode::Shape(Shape::rhombicuboctahedron)),
+    ("truncated cuboctahedron", Mode::Shape(Shape::truncated_cuboctahedron)),
+    ("icosidodecahedron", Mode::Shape(Shape::icosidodecahedron)),
+    ("truncated icosahedron", Mode::Shape(Shape::truncated_icosahedron)),
+    ("3,4-duoprism", Mode::Shape4d(|| Shape4d::duoprism(3, 4))),
+    ("6,6-duoprism", Mode::Shape4d(|| Shape4d::duoprism(6, 6))),
+    ("tetrahedral prism", Mode::Shape4d(Shape4d::tetrahedral_prism)),
+    ("dodecahedral prism", Mode::Shape4d(Shape4d::dodecahedral_prism)),
+    ("torus", Mode::Shape(|| Shape::torus(32, 12))),
+    ("sphere", Mode::Shape(|| Shape::sphere(24, 12))),
+    ("klein bottle", Mode::Shape(|| Shape::klein_bottle(32, 16))),
+    ("möbius strip", Mode::Shape(|| Shape::mobius_strip(32, 4))),
+    ("clifford torus", Mode::Shape4d(|| Shape4d::clifford_torus(24, 24))),
+    ("hopf fibration", Mode::Shape4d(|| Shape4d::hopf_fibration(3, 8, 32))),
+    ("hypercube cross-sections (w/s sweep, hjklin rotate)", Mode::Slice(Shape4d::hypercube)),
+    ("twenty-four-cell cross-sections", Mode::Slice(Shape4d::twentyfourcell)),
+    ("six-hundred-cell cross-sections", Mode::Slice(Shape4d::sixhundredcell)),
+    ("scene: a cube turning inside a cube, beside a hypercube", Mode::Scene(|| builtin_scene(include_str!("../scenes/demo.toml")))),
+    ("scene: a twenty-four-cell keyframed through a timeline", Mode::Scene(|| builtin_scene(include_str!("../scenes/choreography.toml")))),
+    ("script: a hypercube turned by keys (hjklin turn, +/- zoom)", Mode::Script(|| builtin_script(include_str!("../scenes/controls.rhai")))),
+];
 
 fn main() -> ! {
-    let mut buffer =String::new();
-    let mut choice = 0;
-    loop {
-        print!("Pick 1 for cube, 2 for fivecell, 3 for hypercube, and 4 for controllable hypercube: ");
+    let mut buffer = String::new();
+    let args = parse_args();
+    let mode = if let Some(mode) = &args.mode { mode } else { loop {
+        for (i, (name, _)) in MODES.iter().enumerate() {
+            println!("{:>2}: {name}", i + 1);
+        }
+        print!("Pick a number: ");
         io::stdout().flush().unwrap();
+        buffer.clear();
         io::stdin().read_line(&mut buffer).unwrap();
-        match buffer.trim() {
-            "1" => {
-                choice = 1;
-                break;
-            },
-            "2" => {
-                choice = 2;
-                break;
-            },
-            "3" => {
-                choice = 3;
-                break;
-            },
-            "4" => {
-                choice = 4;
-                break;
-            },
+        match buffer.trim().parse::<usize>() {
+            Ok(choice @ 1..) if choice <= MODES.len() => break &MODES[choice - 1].1,
             _ => continue,
         };
+    } };
+    if let Some(path) = &args.record {
+        record(mode, path, args.frames.unwrap_or(RECORD_FRAMES));
+    }
+    if let Some(path) = &args.svg {
+        svg(mode, path);
+    }
+    if let Some(path) = &args.image {
+        image(mode, path, args.width.unwrap_or(IMAGE_WIDTH));
+    }
+    if let Some(path) = &args.text {
+        text(mode, path, args.frames.unwrap_or(RECORD_FRAMES));
+    }
+    if let Some(path) = &args.gif {
+        gif(mode, path, args.frames.unwrap_or(RECORD_FRAMES), args.width.unwrap_or(IMAGE_WIDTH));
     }
     loop {
         buffer.clear();
@@ -42,75 +199,182 @@ fn main() -> ! {
         io::stdout().flush().unwrap();
         io::stdin().read_line(&mut buffer).unwrap();
         match buffer.trim() {
-            "1" => {
-                let screen: Screen<257, 171> = Screen::default();
-                match choice {
-                    1 => screen.init_render_cube(),
-                    2 => screen.init_render_fivecell(),
-                    3 => screen.init_render_hypercube(),
-                    4 => screen.init_render_hypercube_manual(),
-                    _ => unreachable!()
-                }
-                exit(0);
-            },
-            "2" => {
-                let screen: Screen<78, 42> = Screen::default();
-                match choice {
-                    1 => screen.init_render_cube(),
-                    2 => screen.init_render_fivecell(),
-                    3 => screen.init_render_hypercube(),
-                    4 => screen.init_render_hypercube_manual(),
-                    _ => unreachable!()
-                }
-                exit(0);
-            }
+            "1" => run::<257, 171>(mode),
+            "2" => run::<78, 42>(mode),
             _ => continue
         }
     }
 }
 
-#[cfg(test)]
-pub mod test {
-    use std::f128;
-    use crate::{
-        matrix::Matrix3x3,
-        point::Point,
+/// The animation a mode shows, as a scene, whether drawn on the terminal or written out. The
+/// interactive modes have none
+fn animation(mode: &Mode) -> Option<Scene> {
+    let pi = std::f128::consts::PI;
+    // A slow tumble about all three axes, and a double rotation in xy and zw
+    let spin_3d = [pi / 180., pi / 90., pi / 70., 0., 0., 0.];
+    let spin_4d = [pi / 90., 0., 0., 0., 0., pi / 90.];
+    let object = match mode {
+        Mode::Shape(shape) => Object { spin: spin_3d, ..Object::new(shape()) },
+        Mode::Shape4d(shape) => Object { spin: spin_4d, ..Object::new(shape()) },
+        Mode::Polytope(Polytope::Shape(shape)) => Object { spin: spin_3d, ..Object::new(shape.clone()) },
+        Mode::Polytope(Polytope::Shape4d(shape)) => Object { spin: spin_4d, ..Object::new(shape.clone()) },
+        Mode::Model(model @ Model::Mesh(_)) => Object { spin: spin_3d, ..Object::new(model.clone()) },
+        Mode::Model(model @ Model::Mesh4d(_)) => Object { spin: spin_4d, ..Object::new(model.clone()) },
+        Mode::Scene(scene) => return Some(scene()),
+        Mode::SceneFile(scene) => return Some(*scene.clone()),
+        Mode::ManualHypercube | Mode::Calibrate | Mode::Slice(_) | Mode::Script(_) | Mode::ScriptFile(_) => return None,
     };
+    Some(Scene::default().with(object.with_glyph('.')))
+}
 
-    #[test]
-    fn point() {
-        let point = Point([3., 4., 0.]);
-        assert_eq!(point.l2_norm(), 5.);
-        let point = Point([1., 2., 2.]);
-        assert_eq!(point.l2_norm(), 3.);
+/// Writes the mode's animation to an asciinema cast file at the small screen size, then exits
+fn record(mode: &Mode, path: &str, frames: usize) -> ! {
+    let Some(scene) = animation(mode) else {
+        fail(&"only the animated modes can be recorded, not the interactive ones");
+    };
+    let mut screen: Screen<78, 42> = Screen::default();
+    screen.char_ratio = Config::load().char_ratio;
+    let result = File::create(path).and_then(|file| {
+        // One more row than the screen, for the cursor to sit on after the last row is drawn
+        let mut recorder = Recorder::new(BufWriter::new(file), 78, 43, "cubers")?;
+        screen.record_scene(scene, frames, &mut recorder)?;
+        recorder.finish().map(|_| ())
+    });
+    if let Err(err) = result {
+        fail(&format!("{path}: {err}"));
     }
+    exit(0);
+}
 
-    #[test]
-    fn matmul() {
-        let matrix0: Matrix3x3 = Matrix3x3([[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]]);
-        let matrix1: Matrix3x3 = Matrix3x3([[1., 2., 3.], [3., 1., 2.], [2., 3., 1.]]);
+/// Writes the first frame of the mode's animation to an SVG image, then exits
+fn svg(mode: &Mode, path: &str) -> ! {
+    let Some(scene) = animation(mode) else {
+        fail(&"only the animated modes can be drawn as SVG, not the interactive ones");
+    };
+    let mut screen: Screen<78, 42> = Screen::default();
+    screen.char_ratio = Config::load().char_ratio;
+    screen.place_camera(&scene);
+    let scale = screen.scene_scale(&scene);
+    let meshes = scene.meshes().into_iter().map(|mesh| mesh * scale).collect::<Vec<_>>();
+    let svg = screen.render_svg(&meshes, &SvgOptions::default());
+    if let Err(err) = std::fs::write(path, svg) {
+        fail(&format!("{path}: {err}"));
+    }
+    exit(0);
+}
 
-        let swap23:  Matrix3x3 = Matrix3x3([[1., 0., 0.], [0., 0., 1.], [0., 1., 0.]]);
+/// Writes frames of the mode's animation as plain text, then exits. Given `-`, the frames go to
+/// stdout, each followed by a line holding only a form feed. Otherwise each frame is a file in
+/// the directory, numbered from `frame-0000.txt`
+fn text(mode: &Mode, path: &str, frames: usize) -> ! {
+    let Some(scene) = animation(mode) else {
+        fail(&"only the animated modes can be written as text, not the interactive ones");
+    };
+    let mut screen: Screen<78, 42> = Screen::default();
+    screen.char_ratio = Config::load().char_ratio;
+    let mut frames = screen.text_frames(scene, frames);
+    let result = if path == "-" {
+        let mut out = io::stdout().lock();
+        frames
+            .try_for_each(|frame| writeln!(out, "{frame}\x0c"))
+            .and_then(|_| out.flush())
+    } else {
+        std::fs::create_dir_all(path).and_then(|_| {
+            frames.enumerate().try_for_each(|(i, frame)| {
+                std::fs::write(Path::new(path).join(format!("frame-{i:04}.txt")), frame)
+            })
+        })
+    };
+    match result {
+        // Whatever was reading stopped early, as `head` does
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => exit(0),
+        Err(err) => fail(&format!("{path}: {err}")),
+        Ok(()) => exit(0),
+    }
+}
 
-        let matrix2: Matrix3x3 = Matrix3x3([[1., 2., 3.], [2., 3., 1.], [3., 1., 2.]]);
-        let matrix3: Matrix3x3 = Matrix3x3([[1., 3., 2.], [2., 1., 3.], [3., 2., 1.]]);
-        let matrix4: Matrix3x3 = Matrix3x3([[1., 3., 2.], [3., 2., 1.], [2., 1., 3.]]);
+/// Writes the first frame of the mode's animation to a PNG or PPM image, then exits
+fn image(mode: &Mode, path: &str, width: usize) -> ! {
+    let Some(scene) = animation(mode) else {
+        fail(&"only the animated modes can be drawn as images, not the interactive ones");
+    };
+    let mut screen: Screen<78, 42> = Screen::default();
+    screen.char_ratio = Config::load().char_ratio;
+    screen.place_camera(&scene);
+    let scale = screen.scene_scale(&scene);
+    let meshes = scene.meshes().into_iter().map(|mesh| mesh * scale).collect::<Vec<_>>();
+    let image = screen.render_image(&meshes, width);
+    let result = File::create(path).and_then(|file| {
+        let out = BufWriter::new(file);
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("ppm") => image.write_ppm(out),
+            _ => image.write_png(out),
+        }
+    });
+    if let Err(err) = result {
+        fail(&format!("{path}: {err}"));
+    }
+    exit(0);
+}
 
-        assert_eq!(matrix1 * matrix0, matrix1);
-        assert_eq!(matrix0 * matrix1, matrix1);
-        assert_eq!(swap23 * matrix1, matrix2);
+/// Writes one whole turn of every shape in the mode to a looping GIF, then exits
+fn gif(mode: &Mode, path: &str, frames: usize, width: usize) -> ! {
+    let Some(scene) = animation(mode) else {
+        fail(&"only the animated modes can be drawn as GIFs, not the interactive ones");
+    };
+    let mut screen: Screen<78, 42> = Screen::default();
+    screen.char_ratio = Config::load().char_ratio;
+    let tau = std::f128::consts::TAU;
+    let poses = (0..frames)
+        .map(|frame| scene.turned(tau * frame as f128 / frames as f128))
+        .collect::<Vec<_>>();
+    screen.place_camera(&scene);
+    let scale = scene
+        .scale
+        .unwrap_or_else(|| screen.fit_scale(&poses.iter().map(Scene::combined).collect::<Vec<_>>()));
+    let images = poses
+        .iter()
+        .map(|pose| {
+            let meshes = pose.meshes().into_iter().map(|mesh| mesh * scale).collect::<Vec<_>>();
+            screen.render_image(&meshes, width)
+        })
+        .collect::<Vec<Image>>();
+    let result = File::create(path).and_then(|file| {
+        raster::write_gif(&images, FRAME_TIME, BufWriter::new(file))
+    });
+    if let Err(err) = result {
+        fail(&format!("{path}: {err}"));
+    }
+    exit(0);
+}
 
-        assert_eq!(matrix1 * swap23, matrix4);
+/// Compiles one of the scripts built into cubers
+fn builtin_script(source: &str) -> Script {
+    Script::new(source, Path::new(".")).unwrap_or_else(|err| fail(&err))
+}
 
-        assert_eq!(matrix2 * swap23, matrix3);
-    }
+/// Reads one of the scene files built into cubers
+fn builtin_scene(text: &str) -> Scene {
+    scene::file::parse(text, Path::new(".")).unwrap_or_else(|err| fail(&err))
+}
 
-    #[test]
-    fn point_matmul() {
-        let pi = f128::consts::PI;
-        let point = Point([1., 1., 0.]);
-        let xrotate = Matrix3x3::rotate_x_theta(pi/2.);
-        println!("{}", point);
-        println!("{}", xrotate * point);
+fn run<const N: usize, const M: usize>(mode: &Mode) -> ! {
+    let mut screen: Screen<N, M> = Screen::default();
+    screen.char_ratio = Config::load().char_ratio;
+    match animation(mode) {
+        Some(scene) => screen.init_render_scene(scene),
+        // Only the interactive modes have no animation
+        None => match mode {
+            Mode::ManualHypercube => screen.init_render_hypercube_manual(),
+            Mode::Calibrate => screen.init_calibrate(),
+            Mode::Slice(shape) => screen.init_render_slice(shape()),
+            Mode::Script(script) => screen.init_render_script(script()),
+            Mode::ScriptFile(path) => match Script::load(path) {
+                Ok(script) => screen.init_render_script(script),
+                Err(err) => fail(&format!("{}: {err}", path.display())),
+            },
+            _ => {}
+        },
     }
+    exit(0);
 }
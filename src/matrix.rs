@@ -29,6 +29,17 @@ impl Matrix3x3 {
         let sin = theta.sin();
         Matrix3x3([[cos, -sin, 0.], [sin, cos, 0.], [0., 0., 1.]])
     }
+
+    /// Rotation matrix for rotation around an arbitrary axis. Theta is in radians
+    pub fn rotate_about_axis(axis: Point, theta: f128) -> Matrix3x3 {
+        let [x, y, z] = axis.normalize().0;
+        let cos = theta.cos();
+        let sin = theta.sin();
+        let t = 1. - cos;
+        Matrix3x3([[t * x * x + cos,     t * x * y - sin * z, t * x * z + sin * y],
+                   [t * x * y + sin * z, t * y * y + cos,     t * y * z - sin * x],
+                   [t * x * z - sin * y, t * y * z + sin * x, t * z * z + cos]])
+    }
 }
 
 impl Deref for Matrix3x3 {
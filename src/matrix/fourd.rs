@@ -6,6 +6,15 @@ use crate::point::fourd::Point;
 pub struct Matrix4x4(pub [[f128; 4]; 4]);
 
 impl Matrix4x4 {
+    pub fn identity() -> Matrix4x4 {
+        Matrix4x4([
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
     /// Rotation matrix for rotation across the zw plane. Theta is in radians
     pub fn rotate_zw_theta(theta: f128) -> Matrix4x4 {
         let cos = theta.cos();
@@ -0,0 +1,176 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Mul,
+};
+
+use rayon::prelude::*;
+
+use crate::{
+    colour::Colour,
+    line::Line,
+    matrix::Matrix3x3,
+    point::Point,
+    shape::{fourd::Shape as Shape4d, Shape},
+};
+
+pub mod fourd;
+
+/// Fewest vertices a thread transforms at once, so small shapes are not split up for nothing
+pub(crate) const VERTICES_PER_TASK: usize = 64;
+
+/// Whether lines can be drawn in a character: anything but whitespace and control characters
+pub fn is_glyph(char: char) -> bool {
+    !char.is_whitespace() && !char.is_control()
+}
+
+/// A shape stored as a shared list of vertices, with edges and faces referring to them by
+/// index, so each vertex is only transformed once
+#[derive(Debug, Default, Clone)]
+pub struct Mesh {
+    pub vertices: Vec<Point>,
+    pub edges: Vec<[usize; 2]>,
+    /// Polygons as lists of vertex indices, in order around their boundary. Empty if unknown
+    pub faces: Vec<Vec<usize>>,
+    pub glyph: char,
+    pub colour: Option<Colour>,
+}
+
+/// A mesh that may be either 3d or 4d
+#[derive(Debug, Clone)]
+pub enum Model {
+    Mesh(Mesh),
+    Mesh4d(fourd::Mesh),
+}
+
+impl From<Mesh> for Model {
+    fn from(mesh: Mesh) -> Self {
+        Model::Mesh(mesh)
+    }
+}
+
+impl From<fourd::Mesh> for Model {
+    fn from(mesh: fourd::Mesh) -> Self {
+        Model::Mesh4d(mesh)
+    }
+}
+
+impl From<Shape> for Model {
+    fn from(shape: Shape) -> Self {
+        Model::Mesh(Mesh::from(shape))
+    }
+}
+
+impl From<Shape4d> for Model {
+    fn from(shape: Shape4d) -> Self {
+        Model::Mesh4d(fourd::Mesh::from(shape))
+    }
+}
+
+impl From<&Shape> for Mesh {
+    /// Merges line endpoints that are the same point and drops repeated lines
+    fn from(shape: &Shape) -> Self {
+        let mut mesh = Mesh {
+            glyph: shape.1,
+            ..Mesh::default()
+        };
+        let mut indices = HashMap::new();
+        let mut seen = HashSet::new();
+        for line in shape.iter() {
+            let [a, b] = line.map(|point| {
+                *indices.entry(point.grid_key()).or_insert_with(|| {
+                    mesh.vertices.push(point);
+                    mesh.vertices.len() - 1
+                })
+            });
+            if a != b && seen.insert([a.min(b), a.max(b)]) {
+                mesh.edges.push([a, b]);
+            }
+        }
+        mesh
+    }
+}
+
+impl From<Shape> for Mesh {
+    fn from(shape: Shape) -> Self {
+        Mesh::from(&shape)
+    }
+}
+
+impl From<&Mesh> for Shape {
+    fn from(mesh: &Mesh) -> Self {
+        Shape(mesh.lines().collect(), mesh.glyph)
+    }
+}
+
+impl Mul<f128> for Mesh {
+    type Output = Mesh;
+
+    fn mul(self, rhs: f128) -> Self::Output {
+        self.map_vertices(|point| point * rhs)
+    }
+}
+
+impl Mesh {
+    /// The edges as lines between their endpoints
+    pub fn lines(&self) -> impl Iterator<Item = Line> + '_ {
+        self.edges
+            .iter()
+            .map(|[a, b]| Line([self.vertices[*a], self.vertices[*b]]))
+    }
+
+    /// Moves every vertex, keeping the edges and faces between them. The vertices are
+    /// moved across the thread pool
+    pub fn map_vertices(self, f: impl Fn(Point) -> Point + Sync + Send) -> Mesh {
+        Mesh {
+            vertices: self.vertices.into_par_iter().with_min_len(VERTICES_PER_TASK).map(f).collect(),
+            ..self
+        }
+    }
+
+    pub fn transform(self, matrix: Matrix3x3) -> Mesh {
+        self.map_vertices(|point| matrix * point)
+    }
+
+    /// Rotate mesh around the x axis theta radians
+    pub fn rotate_x_theta(self, theta: f128) -> Mesh {
+        self.transform(Matrix3x3::rotate_x_theta(theta))
+    }
+
+    /// Rotate mesh around the y axis theta radians
+    pub fn rotate_y_theta(self, theta: f128) -> Mesh {
+        self.transform(Matrix3x3::rotate_y_theta(theta))
+    }
+
+    /// Rotate mesh around the z axis theta radians
+    pub fn rotate_z_theta(self, theta: f128) -> Mesh {
+        self.transform(Matrix3x3::rotate_z_theta(theta))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{fourd::Mesh as Mesh4d, Mesh};
+    use crate::shape::{fourd::Shape as Shape4d, Shape};
+
+    #[test]
+    fn from_shape() {
+        let cube = Mesh::from(Shape::cube());
+        assert_eq!((cube.vertices.len(), cube.edges.len()), (8, 12));
+        assert_eq!(Shape::from(&cube).len(), 12);
+
+        let hypercube = Mesh4d::from(Shape4d::hypercube());
+        assert_eq!((hypercube.vertices.len(), hypercube.edges.len()), (16, 32));
+    }
+
+    #[test]
+    fn rotation_matches_shape() {
+        let theta = std::f128::consts::PI / 7.;
+        let shape = Shape4d::hypercube().rotate_xw_theta(theta).to_shape_3d();
+        let mesh = Mesh4d::from(Shape4d::hypercube()).rotate_xw_theta(theta).to_mesh_3d();
+        for (line, mesh_line) in shape.iter().zip(mesh.lines()) {
+            for (a, b) in line.iter().zip(mesh_line.iter()) {
+                assert!((*a - *b).l2_norm() < 1e-20);
+            }
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Mul,
+};
+
+use rayon::prelude::*;
+
+use super::{Mesh as Mesh3d, VERTICES_PER_TASK};
+use crate::{
+    colour::Colour,
+    hull::order_polygon,
+    line::fourd::Line,
+    matrix::fourd::Matrix4x4,
+    point::{fourd::Point, Point as Point3d},
+    shape::fourd::Shape,
+};
+
+/// A 4d shape stored as a shared list of vertices, with edges, faces and cells referring to
+/// them by index
+#[derive(Debug, Default, Clone)]
+pub struct Mesh {
+    pub vertices: Vec<Point>,
+    pub edges: Vec<[usize; 2]>,
+    /// Polygons as lists of vertex indices, in order around their boundary. Empty if unknown
+    pub faces: Vec<Vec<usize>>,
+    /// 3d cells bounding the shape, as the indices of the faces around each. Empty if unknown
+    pub cells: Vec<Vec<usize>>,
+    pub glyph: char,
+    pub colour: Option<Colour>,
+}
+
+impl From<&Shape> for Mesh {
+    /// Merges line endpoints that are the same point and drops repeated lines
+    fn from(shape: &Shape) -> Self {
+        let mut mesh = Mesh {
+            glyph: shape.1,
+            ..Mesh::default()
+        };
+        let mut indices = HashMap::new();
+        let mut seen = HashSet::new();
+        for line in shape.iter() {
+            let [a, b] = line.map(|point| {
+                *indices.entry(point.grid_key()).or_insert_with(|| {
+                    mesh.vertices.push(point);
+                    mesh.vertices.len() - 1
+                })
+            });
+            if a != b && seen.insert([a.min(b), a.max(b)]) {
+                mesh.edges.push([a, b]);
+            }
+        }
+        mesh
+    }
+}
+
+impl From<Shape> for Mesh {
+    fn from(shape: Shape) -> Self {
+        Mesh::from(&shape)
+    }
+}
+
+impl From<&Mesh> for Shape {
+    fn from(mesh: &Mesh) -> Self {
+        Shape(mesh.lines().collect(), mesh.glyph)
+    }
+}
+
+impl Mul<f128> for Mesh {
+    type Output = Mesh;
+
+    fn mul(self, rhs: f128) -> Self::Output {
+        self.map_vertices(|point| point * rhs)
+    }
+}
+
+impl Mesh {
+    /// The edges as lines between their endpoints
+    pub fn lines(&self) -> impl Iterator<Item = Line> + '_ {
+        self.edges
+            .iter()
+            .map(|[a, b]| Line([self.vertices[*a], self.vertices[*b]]))
+    }
+
+    /// Moves every vertex, keeping the edges, faces and cells between them. The vertices are
+    /// moved across the thread pool
+    pub fn map_vertices(self, f: impl Fn(Point) -> Point + Sync + Send) -> Mesh {
+        Mesh {
+            vertices: self.vertices.into_par_iter().with_min_len(VERTICES_PER_TASK).map(f).collect(),
+            ..self
+        }
+    }
+
+    pub fn transform(self, matrix: Matrix4x4) -> Mesh {
+        self.map_vertices(|point| matrix * point)
+    }
+
+    /// Projects the mesh into 3d. Edges crossing the eye hyperplane are cut short, and
+    /// anything entirely behind it is dropped, along with faces that are not wholly in front
+    pub fn to_mesh_3d(&self) -> Mesh3d {
+        let project = |point: Point| Point3d(point.to_cube_xyz().into());
+
+        let mut mesh = Mesh3d {
+            vertices: self.vertices.iter().map(|point| project(*point)).collect(),
+            glyph: self.glyph,
+            colour: self.colour,
+            ..Mesh3d::default()
+        };
+        let visible = self
+            .vertices
+            .iter()
+            .map(|point| point.eye_clip_distance() >= 0.)
+            .collect::<Vec<_>>();
+        for &[a, b] in &self.edges {
+            match (visible[a], visible[b]) {
+                (true, true) => mesh.edges.push([a, b]),
+                (false, false) => (),
+                _ => {
+                    let Some(clipped) = Line([self.vertices[a], self.vertices[b]]).clip_to_eye()
+                    else {
+                        continue;
+                    };
+                    let (kept, crossing) = if visible[a] { (a, clipped[1]) } else { (b, clipped[0]) };
+                    mesh.vertices.push(project(crossing));
+                    mesh.edges.push([kept, mesh.vertices.len() - 1]);
+                }
+            }
+        }
+        mesh.faces = self
+            .faces
+            .iter()
+            .filter(|face| face.iter().all(|i| visible[*i]))
+            .cloned()
+            .collect();
+        mesh
+    }
+
+    /// The cross-section where the hyperplane of points p with `normal · p = offset` cuts the
+    /// mesh, in coordinates within the hyperplane, so a normal along w keeps x, y and z. The
+    /// normal is scaled to unit length first. Each face the hyperplane crosses gives an edge
+    /// of the section and each cell a face, so this needs convex faces and cells, as made by
+    /// `Mesh::convex_hull`
+    pub fn slice(&self, normal: Point, offset: f128) -> Mesh3d {
+        let normal = normal.normalize();
+        let basis = hyperplane_basis(normal);
+        let side = self
+            .vertices
+            .iter()
+            .map(|point| point.dot(normal) - offset)
+            .collect::<Vec<_>>();
+        let on_plane = |i: usize| side[i].abs() < 1e-12;
+
+        // Section vertices are either mesh vertices on the hyperplane, keyed by [i, i], or
+        // crossings of edges from one side to the other, keyed by their ends
+        let mut vertices = vec![];
+        let mut indices = HashMap::new();
+        let mut vertex = |a: usize, b: usize| {
+            *indices.entry([a.min(b), a.max(b)]).or_insert_with(|| {
+                let point = if a == b {
+                    self.vertices[a]
+                } else {
+                    let t = side[a] / (side[a] - side[b]);
+                    self.vertices[a] + (self.vertices[b] - self.vertices[a]) * t
+                };
+                vertices.push(Point3d(basis.map(|axis| point.dot(axis))));
+                vertices.len() - 1
+            })
+        };
+
+        let mut edges = vec![];
+        let mut seen = HashSet::new();
+        let mut face_edges = vec![vec![]; self.faces.len()];
+        for (face, face_lines) in self.faces.iter().zip(&mut face_edges) {
+            let mut crossings = vec![];
+            for (&a, &b) in face.iter().zip(face.iter().cycle().skip(1)) {
+                let crossing = if on_plane(a) {
+                    vertex(a, a)
+                } else if !on_plane(b) && side[a].signum() != side[b].signum() {
+                    vertex(a, b)
+                } else {
+                    continue;
+                };
+                if !crossings.contains(&crossing) {
+                    crossings.push(crossing);
+                }
+            }
+            // A face crossing the hyperplane meets it in a line, and a face lying in it is
+            // drawn around its boundary
+            let lines = match crossings.len() {
+                2 => vec![[crossings[0], crossings[1]]],
+                3.. if face.iter().all(|i| on_plane(*i)) => crossings
+                    .iter()
+                    .zip(crossings.iter().cycle().skip(1))
+                    .map(|(a, b)| [*a, *b])
+                    .collect(),
+                _ => vec![],
+            };
+            for [a, b] in lines {
+                if seen.insert([a.min(b), a.max(b)]) {
+                    edges.push([a, b]);
+                }
+                face_lines.push([a, b]);
+            }
+        }
+
+        let mut faces = vec![];
+        let mut seen_faces = HashSet::new();
+        for cell in &self.cells {
+            let in_plane = cell
+                .iter()
+                .all(|face| self.faces[*face].iter().all(|i| on_plane(*i)));
+            if in_plane {
+                continue;
+            }
+            let cell_edges = cell
+                .iter()
+                .flat_map(|face| face_edges[*face].iter().copied())
+                .collect::<Vec<_>>();
+            let mut polygon = cell_edges.iter().flatten().copied().collect::<Vec<_>>();
+            polygon.sort();
+            polygon.dedup();
+            // Two cells meeting at a face in the hyperplane both give that face
+            if polygon.len() >= 3 && seen_faces.insert(polygon.clone()) {
+                faces.push(order_polygon(&polygon, &cell_edges));
+            }
+        }
+        Mesh3d {
+            vertices,
+            edges,
+            faces,
+            glyph: self.glyph,
+            colour: self.colour,
+        }
+    }
+
+    /// Rotate mesh around the zw plane theta radians
+    pub fn rotate_zw_theta(self, theta: f128) -> Mesh {
+        self.transform(Matrix4x4::rotate_zw_theta(theta))
+    }
+
+    /// Rotate mesh around the yw plane theta radians
+    pub fn rotate_yw_theta(self, theta: f128) -> Mesh {
+        self.transform(Matrix4x4::rotate_yw_theta(theta))
+    }
+
+    /// Rotate mesh around the yz plane theta radians
+    pub fn rotate_yz_theta(self, theta: f128) -> Mesh {
+        self.transform(Matrix4x4::rotate_yz_theta(theta))
+    }
+
+    /// Rotate mesh around the xw plane theta radians
+    pub fn rotate_xw_theta(self, theta: f128) -> Mesh {
+        self.transform(Matrix4x4::rotate_xw_theta(theta))
+    }
+
+    /// Rotate mesh around the xy plane theta radians
+    pub fn rotate_xy_theta(self, theta: f128) -> Mesh {
+        self.transform(Matrix4x4::rotate_xy_theta(theta))
+    }
+
+    /// Rotate mesh around the xz plane theta radians
+    pub fn rotate_xz_theta(self, theta: f128) -> Mesh {
+        self.transform(Matrix4x4::rotate_xz_theta(theta))
+    }
+}
+
+/// Three unit vectors at right angles to each other and to the unit vector `normal`, found by
+/// removing the normal from the coordinate axes and keeping the three longest that remain.
+/// Ties keep coordinate order, so a normal along w gives the x, y and z axes
+fn hyperplane_basis(normal: Point) -> [Point; 3] {
+    let mut axes = (0..4)
+        .map(|i| {
+            let axis = Point(std::array::from_fn(|j| if i == j { 1. } else { 0. }));
+            axis - normal * normal[i]
+        })
+        .collect::<Vec<_>>();
+    axes.sort_by(|a, b| b.l2_norm().total_cmp(&a.l2_norm()));
+    let mut basis: [Point; 3] = Default::default();
+    for i in 0..3 {
+        let mut axis = axes[i];
+        for done in &basis[..i] {
+            axis = axis - *done * axis.dot(*done);
+        }
+        basis[i] = axis.normalize();
+    }
+    basis
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{Mesh, Mesh3d};
+    use crate::{point::fourd::Point, shape::fourd::Shape};
+
+    #[test]
+    fn slice() {
+        let counts = |mesh: Mesh3d| (mesh.vertices.len(), mesh.edges.len(), mesh.faces.len());
+        let hypercube = Mesh::convex_hull(&Shape::hypercube().vertices()).unwrap();
+        let w = Point([0., 0., 0., 1.]);
+        assert_eq!(counts(hypercube.slice(w, 0.)), (8, 12, 6));
+        // Through a cell, which is drawn, with the neighbouring cells meeting it in faces
+        assert_eq!(counts(hypercube.slice(w, 1.)), (8, 12, 6));
+        assert_eq!(counts(hypercube.slice(w, 1.5)), (0, 0, 0));
+        // Halfway along the long diagonal the section is a regular octahedron
+        assert_eq!(counts(hypercube.slice(Point([1., 1., 1., 1.]), 0.)), (6, 12, 8));
+        // Near a corner it is a tetrahedron
+        assert_eq!(counts(hypercube.slice(Point([1., 1., 1., 1.]), 1.8)), (4, 6, 4));
+
+        let sixteencell = Mesh::convex_hull(&Shape::sixteencell().vertices()).unwrap();
+        let octahedron = sixteencell.slice(w, 0.);
+        assert_eq!(counts(octahedron.clone()), (6, 12, 8));
+        assert!(octahedron.vertices.iter().all(|point| (point.l2_norm() - 1.).abs() < 1e-20));
+    }
+}
@@ -1,6 +1,7 @@
-use std::{f128, fmt::Display, ops::{Add, Deref, DerefMut, Mul}};
+use std::{f128, fmt::Display, ops::{Add, Deref, DerefMut, Mul, Sub}};
 
 use crate::matrix::Matrix3x3;
+use crate::point::fourd::Point as Point4d;
 
 pub mod fourd;
 
@@ -33,6 +34,13 @@ impl Add for Point {
     }
 }
 
+impl Sub for Point {
+    type Output = Point;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Point([self[0] - rhs[0], self[1] - rhs[1], self[2] - rhs[2]])
+    }
+}
+
 impl Mul for Point {
     type Output = Point;
     fn mul(self, rhs: Self) -> Self::Output {
@@ -57,6 +65,30 @@ impl Point {
         (self[0].powi(2) + self[1].powi(2) + self[2].powi(2)).sqrt()
     }
 
+    /// Coordinates rounded to a fixed precision, so nearly equal points can be hashed together
+    pub fn grid_key(&self) -> [i64; 3] {
+        self.map(|x| (x * 1e9).round() as i64)
+    }
+
+    pub fn dot(&self, rhs: Point) -> f128 {
+        self[0] * rhs[0] + self[1] * rhs[1] + self[2] * rhs[2]
+    }
+
+    pub fn cross(&self, rhs: Point) -> Point {
+        Point([self[1] * rhs[2] - self[2] * rhs[1],
+               self[2] * rhs[0] - self[0] * rhs[2],
+               self[0] * rhs[1] - self[1] * rhs[0]])
+    }
+
+    /// Scale point to unit length. The zero vector is returned unchanged
+    pub fn normalize(self) -> Point {
+        let norm = self.l2_norm();
+        if norm == 0. {
+            return self;
+        }
+        self * (1. / norm)
+    }
+
     /// Rotate point around the x axis theta radians
     pub fn rotate_x_theta(self, theta: f128) -> Point {
         Matrix3x3::rotate_x_theta(theta) * self
@@ -72,9 +104,14 @@ impl Point {
         Matrix3x3::rotate_z_theta(theta) * self
     }
 
-    pub fn to_screen_xy(&self) -> (f128, f128) {
-        (self[0] * 2. / (4. + self[1]), 
-         self[2] * 2. / (4. + self[1]))
+    /// The point in homogeneous coordinates, for use with 4x4 transformation matrices
+    pub fn homogeneous(&self) -> Point4d {
+        Point4d([self[0], self[1], self[2], 1.])
+    }
+
+    /// Rotate point around an arbitrary axis theta radians
+    pub fn rotate_about(self, axis: Point, theta: f128) -> Point {
+        Matrix3x3::rotate_about_axis(axis, theta) * self
     }
 }
 
@@ -1,11 +1,16 @@
 use std::{
     f128,
     fmt::Display,
-    ops::{Add, Deref, DerefMut, Mul},
+    ops::{Add, Deref, DerefMut, Mul, Sub},
 };
 
 use crate::matrix::fourd::Matrix4x4;
 
+/// Distance of the 4d eye from the origin along -w, used when projecting into 3d
+pub const EYE_DISTANCE: f128 = 4.;
+/// How close to the eye hyperplane a point may get before it is clipped away
+pub const EYE_NEAR: f128 = 0.1;
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Point(pub [f128; 4]);
 
@@ -44,6 +49,18 @@ impl Add for Point {
     }
 }
 
+impl Sub for Point {
+    type Output = Point;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Point([
+            self[0] - rhs[0],
+            self[1] - rhs[1],
+            self[2] - rhs[2],
+            self[3] - rhs[3],
+        ])
+    }
+}
+
 impl Mul for Point {
     type Output = Point;
     fn mul(self, rhs: Self) -> Self::Output {
@@ -66,11 +83,29 @@ impl Mul<f128> for Point {
 
 impl Point {
     pub fn l1_norm(&self) -> f128 {
-        self[0].abs() + self[1].abs() + self[2].abs()
+        self[0].abs() + self[1].abs() + self[2].abs() + self[3].abs()
     }
 
     pub fn l2_norm(&self) -> f128 {
-        (self[0].powi(2) + self[1].powi(2) + self[2].powi(2)).sqrt()
+        (self[0].powi(2) + self[1].powi(2) + self[2].powi(2) + self[3].powi(2)).sqrt()
+    }
+
+    /// Coordinates rounded to a fixed precision, so nearly equal points can be hashed together
+    pub fn grid_key(&self) -> [i64; 4] {
+        self.map(|x| (x * 1e9).round() as i64)
+    }
+
+    pub fn dot(&self, rhs: Point) -> f128 {
+        self[0] * rhs[0] + self[1] * rhs[1] + self[2] * rhs[2] + self[3] * rhs[3]
+    }
+
+    /// Scale point to unit length. The zero vector is returned unchanged
+    pub fn normalize(self) -> Point {
+        let norm = self.l2_norm();
+        if norm == 0. {
+            return self;
+        }
+        self * (1. / norm)
     }
 
     /// Rotate point around the zw plane theta radians
@@ -103,11 +138,18 @@ impl Point {
         Matrix4x4::rotate_xz_theta(theta) * self
     }
 
-    // NOTE: Maybe?
+    /// Signed distance in front of the plane that lines are clipped to before projecting
+    pub fn eye_clip_distance(&self) -> f128 {
+        self[3] + EYE_DISTANCE - EYE_NEAR
+    }
+
+    /// Perspective projection from an eye at w = -EYE_DISTANCE. Points at or behind the eye
+    /// have no sensible projection, so lines should be clipped with `Line::clip_to_eye` first
     pub fn to_cube_xyz(&self) -> (f128, f128, f128) {
-        (self[0] * 2. / (4. + self[3]), 
-         self[1] * 2. / (4. + self[3]),
-         self[2] * 2. / (4. + self[3]))
+        let depth = EYE_DISTANCE + self[3];
+        (self[0] * 2. / depth,
+         self[1] * 2. / depth,
+         self[2] * 2. / depth)
     }
 
     // NOTE: Maybe?
@@ -0,0 +1,274 @@
+use std::{f128::consts::PI, ops::Mul};
+
+use crate::{matrix::Matrix3x3, point::Point};
+
+pub mod fourd;
+
+/// A unit quaternion, standing for a rotation in 3d. Unlike a matrix, two of them can be
+/// blended smoothly with `slerp`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub w: f128,
+    pub x: f128,
+    pub y: f128,
+    pub z: f128,
+}
+
+impl Default for Quaternion {
+    /// No rotation
+    fn default() -> Self {
+        Quaternion::identity()
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    /// The rotation `rhs` followed by `self`
+    fn mul(self, rhs: Quaternion) -> Self::Output {
+        let Quaternion { w, x, y, z } = self;
+        Quaternion {
+            w: w * rhs.w - x * rhs.x - y * rhs.y - z * rhs.z,
+            x: w * rhs.x + x * rhs.w + y * rhs.z - z * rhs.y,
+            y: w * rhs.y - x * rhs.z + y * rhs.w + z * rhs.x,
+            z: w * rhs.z + x * rhs.y - y * rhs.x + z * rhs.w,
+        }
+    }
+}
+
+impl Quaternion {
+    pub fn identity() -> Quaternion {
+        Quaternion {
+            w: 1.,
+            x: 0.,
+            y: 0.,
+            z: 0.,
+        }
+    }
+
+    /// Rotation around an axis by theta radians, turning anticlockwise looking down the axis
+    pub fn about_axis(axis: Point, theta: f128) -> Quaternion {
+        let [x, y, z] = axis.normalize().0;
+        let (sin, cos) = (theta / 2.).sin_cos();
+        Quaternion {
+            w: cos,
+            x: x * sin,
+            y: y * sin,
+            z: z * sin,
+        }
+    }
+
+    /// Rotation by theta radians in the plane of two axes, turning the first towards the
+    /// second. Axis 3 is the real part, as a quaternion's w is the fourth coordinate of a
+    /// point in 4d
+    fn plane(a: usize, b: usize, theta: f128) -> Quaternion {
+        let mut axis = Point::default();
+        axis[a] = 1.;
+        let mut towards = Point::default();
+        towards[b] = 1.;
+        Quaternion::about_axis(axis.cross(towards), theta)
+    }
+
+    /// The same turns as `Mesh::rotate_z_theta(xy).rotate_y_theta(xz).rotate_x_theta(yz)`
+    pub fn from_angles([xy, xz, yz]: [f128; 3]) -> Quaternion {
+        Quaternion::plane(1, 2, yz) * Quaternion::plane(2, 0, xz) * Quaternion::plane(0, 1, xy)
+    }
+
+    pub fn conjugate(self) -> Quaternion {
+        Quaternion {
+            w: self.w,
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+
+    pub fn dot(self, rhs: Quaternion) -> f128 {
+        self.w * rhs.w + self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    pub fn scale(self, factor: f128) -> Quaternion {
+        Quaternion {
+            w: self.w * factor,
+            x: self.x * factor,
+            y: self.y * factor,
+            z: self.z * factor,
+        }
+    }
+
+    fn add(self, rhs: Quaternion) -> Quaternion {
+        Quaternion {
+            w: self.w + rhs.w,
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+
+    pub fn normalize(self) -> Quaternion {
+        self.scale(1. / self.dot(self).sqrt())
+    }
+
+    /// Spherical linear interpolation, turning at a steady rate from `self` at t = 0 to `end`
+    /// at t = 1 along the great arc between them. This does not take the shorter way round, as
+    /// `q` and `-q` are different quaternions even when they are the same rotation
+    pub fn slerp(self, end: Quaternion, t: f128) -> Quaternion {
+        let cos = self.dot(end).clamp(-1., 1.);
+        // Nearly the same, where the sines below vanish
+        if cos > 0.9995 {
+            return self.scale(1. - t).add(end.scale(t)).normalize();
+        }
+        // Opposite ends, where every great arc between them is as short as any other, so go
+        // through a quaternion at right angles to `self`
+        if cos < -1. + 1e-9 {
+            let across = Quaternion {
+                w: -self.x,
+                x: self.w,
+                y: -self.z,
+                z: self.y,
+            };
+            return self.scale((t * PI).cos()).add(across.scale((t * PI).sin()));
+        }
+        let angle = cos.acos();
+        let sin = angle.sin();
+        self.scale(((1. - t) * angle).sin() / sin)
+            .add(end.scale((t * angle).sin() / sin))
+    }
+
+    /// The rotation `self` turned into the rotation `end`, by the shorter way round
+    pub fn slerp_rotation(self, end: Quaternion, t: f128) -> Quaternion {
+        let end = if self.dot(end) < 0. {
+            end.scale(-1.)
+        } else {
+            end
+        };
+        self.slerp(end, t)
+    }
+
+    pub fn rotate(self, point: Point) -> Point {
+        let [x, y, z] = point.0;
+        let rotated = self * Quaternion { w: 0., x, y, z } * self.conjugate();
+        Point([rotated.x, rotated.y, rotated.z])
+    }
+
+    pub fn to_matrix(self) -> Matrix3x3 {
+        let mut matrix = Matrix3x3::default();
+        for column in 0..3 {
+            let mut axis = Point::default();
+            axis[column] = 1.;
+            let image = self.rotate(axis);
+            for row in 0..3 {
+                matrix[row][column] = image[row];
+            }
+        }
+        matrix
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::f128::consts::{FRAC_PI_2, PI};
+
+    use super::{fourd::Rotation, Quaternion};
+    use crate::{
+        matrix::{fourd::Matrix4x4, Matrix3x3},
+        point::{fourd::Point as Point4d, Point},
+    };
+
+    fn close<const N: usize>(a: [[f128; N]; N], b: [[f128; N]; N]) -> bool {
+        a.iter()
+            .flatten()
+            .zip(b.iter().flatten())
+            .all(|(a, b)| (a - b).abs() < 1e-9)
+    }
+
+    #[test]
+    fn quaternions() {
+        let angles = [0.3, -1.1, 2.];
+        let matrix = Matrix3x3::rotate_x_theta(2.)
+            * Matrix3x3::rotate_y_theta(-1.1)
+            * Matrix3x3::rotate_z_theta(0.3);
+        assert!(close(
+            Quaternion::from_angles(angles).to_matrix().0,
+            matrix.0
+        ));
+
+        let start = Quaternion::identity();
+        let end = Quaternion::from_angles([PI, 0., 0.]);
+        let halfway = start.slerp_rotation(end, 0.5);
+        assert!(close(
+            halfway.to_matrix().0,
+            Matrix3x3::rotate_z_theta(FRAC_PI_2).0
+        ));
+        // The same rotation from either sign of quaternion
+        let end = end.scale(-1.);
+        assert!(close(
+            start.slerp_rotation(end, 0.5).to_matrix().0,
+            halfway.to_matrix().0
+        ));
+        let point =
+            Quaternion::about_axis(Point([0., 0., 1.]), FRAC_PI_2).rotate(Point([1., 0., 0.]));
+        assert!((point - Point([0., 1., 0.])).l2_norm() < 1e-9);
+    }
+
+    /// Turns axis a towards axis b by theta radians, leaving the other two axes alone
+    fn turn(a: usize, b: usize, theta: f128) -> Matrix4x4 {
+        let mut matrix = Matrix4x4::identity();
+        matrix[a][a] = theta.cos();
+        matrix[b][b] = theta.cos();
+        matrix[b][a] = theta.sin();
+        matrix[a][b] = -theta.sin();
+        matrix
+    }
+
+    #[test]
+    fn rotations_4d() {
+        let planes = [(0, 1), (2, 0), (1, 2), (0, 3), (1, 3), (2, 3)];
+        for (slot, &(a, b)) in planes.iter().enumerate() {
+            let mut angles = [0.; 6];
+            angles[slot] = 0.7;
+            assert!(close(
+                Rotation::from_angles(angles).to_matrix().0,
+                turn(a, b, 0.7).0
+            ));
+        }
+
+        let angles = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6];
+        let matrix = planes
+            .iter()
+            .zip(angles)
+            .fold(Matrix4x4::identity(), |matrix, (&(a, b), theta)| {
+                turn(a, b, theta) * matrix
+            });
+        let rotation = Rotation::from_angles(angles);
+        assert!(close(rotation.to_matrix().0, matrix.0));
+        let point = Point4d([1., 2., 3., 4.]);
+        assert!((rotation.rotate(point) - matrix * point).l2_norm() < 1e-9);
+
+        // The 3d planes turn a 4d point's xyz just as they turn a 3d point
+        let rotation = Rotation::from_angles([0.1, 0.2, 0.3, 0., 0., 0.]);
+        let turned = rotation.rotate(Point4d([1., 2., 3., 4.]));
+        let expected = Quaternion::from_angles([0.1, 0.2, 0.3]).rotate(Point([1., 2., 3.]));
+        assert!((Point([turned[0], turned[1], turned[2]]) - expected).l2_norm() < 1e-9);
+        assert!((turned[3] - 4.).abs() < 1e-9);
+
+        // Halfway through a double rotation is half of each turn
+        let end = Rotation::from_angles([0., 0., 0., 0., 0., PI])
+            * Rotation::from_angles([PI / 2., 0., 0., 0., 0., 0.]);
+        let halfway = Rotation::default().slerp(end, 0.5);
+        let expected = turn(2, 3, PI / 2.) * turn(0, 1, PI / 4.);
+        assert!(close(halfway.to_matrix().0, expected.0));
+
+        // Half a turn in both xy and zw sends every point to its opposite, from either half
+        // being the negative of the identity's. Halfway there is some turn that does it twice
+        let end = Rotation::from_angles([PI, 0., 0., 0., 0., PI]);
+        let mut opposite = Matrix4x4::identity();
+        for i in 0..4 {
+            opposite[i][i] = -1.;
+        }
+        assert!(close(end.to_matrix().0, opposite.0));
+        let halfway = Rotation::default().slerp(end, 0.5);
+        assert!(close((halfway * halfway).to_matrix().0, end.to_matrix().0));
+        assert!(close(Rotation::default().slerp(end, 1.).to_matrix().0, end.to_matrix().0));
+    }
+}
@@ -0,0 +1,100 @@
+use std::ops::Mul;
+
+use super::Quaternion;
+use crate::{
+    matrix::fourd::Matrix4x4,
+    point::{fourd::Point, Point as Point3d},
+};
+
+/// A rotation in 4d, as the pair of unit quaternions multiplied on either side of a point.
+/// Every 4d rotation can be written this way, and blending the two halves separately gives a
+/// smooth turn from one orientation to another
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rotation {
+    pub left: Quaternion,
+    pub right: Quaternion,
+}
+
+impl Mul for Rotation {
+    type Output = Rotation;
+
+    /// The rotation `rhs` followed by `self`
+    fn mul(self, rhs: Rotation) -> Self::Output {
+        Rotation {
+            left: self.left * rhs.left,
+            right: rhs.right * self.right,
+        }
+    }
+}
+
+impl Rotation {
+    /// Rotation by theta radians in the plane of two axes, turning the first towards the
+    /// second, where axes 0 to 3 are x, y, z and w
+    fn plane(a: usize, b: usize, theta: f128) -> Rotation {
+        if b == 3 {
+            // Turning towards w is the same on both sides
+            let mut axis = [0.; 3];
+            axis[a] = 1.;
+            let half = Quaternion::about_axis(Point3d(axis), -theta);
+            Rotation {
+                left: half,
+                right: half,
+            }
+        } else {
+            let turn = Quaternion::plane(a, b, theta);
+            Rotation {
+                left: turn,
+                right: turn.conjugate(),
+            }
+        }
+    }
+
+    /// Turns in the xy, xz, yz, xw, yw and zw planes in turn, as `Object::spin` does. The
+    /// first three turn the same way as `Quaternion::from_angles`
+    pub fn from_angles(angles: [f128; 6]) -> Rotation {
+        // xz turns z towards x, as `Matrix3x3::rotate_y_theta` does
+        let planes = [(0, 1), (2, 0), (1, 2), (0, 3), (1, 3), (2, 3)];
+        planes
+            .iter()
+            .zip(angles)
+            .fold(Rotation::default(), |rotation, (&(a, b), theta)| {
+                Rotation::plane(a, b, theta) * rotation
+            })
+    }
+
+    /// Turns from `self` at t = 0 to `end` at t = 1. Negating both halves gives the same
+    /// rotation, which is used to take the shorter way round for the two halves together
+    pub fn slerp(self, end: Rotation, t: f128) -> Rotation {
+        let end = if self.left.dot(end.left) + self.right.dot(end.right) < 0. {
+            Rotation {
+                left: end.left.scale(-1.),
+                right: end.right.scale(-1.),
+            }
+        } else {
+            end
+        };
+        Rotation {
+            left: self.left.slerp(end.left, t),
+            right: self.right.slerp(end.right, t),
+        }
+    }
+
+    pub fn rotate(self, point: Point) -> Point {
+        let [x, y, z, w] = point.0;
+        let rotated = self.left * Quaternion { w, x, y, z } * self.right;
+        Point([rotated.x, rotated.y, rotated.z, rotated.w])
+    }
+
+    pub fn to_matrix(self) -> Matrix4x4 {
+        let mut matrix = Matrix4x4::default();
+        for column in 0..4 {
+            let mut axis = Point::default();
+            axis[column] = 1.;
+            let image = self.rotate(axis);
+            for row in 0..4 {
+                matrix[row][column] = image[row];
+            }
+        }
+        matrix
+    }
+}
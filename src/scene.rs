@@ -0,0 +1,284 @@
+use crate::{
+    camera::Camera,
+    colour::Colour,
+    mesh::{Mesh, Model},
+    point::Point,
+    timeline::{Timeline, Track},
+};
+
+pub mod file;
+
+/// One shape in a scene, with where it sits, how big it is and how it turns
+#[derive(Debug, Clone)]
+pub struct Object {
+    pub model: Model,
+    /// Radians to turn by each frame in the xy, xz, yz, xw, yw and zw planes, in that order.
+    /// The first three turn 3d and 4d shapes alike and the last three only turn 4d shapes
+    pub spin: [f128; 6],
+    pub scale: f128,
+    /// Where the centre of the shape sits in the 3d scene
+    pub position: Point,
+    /// Keyframed orientation, scale and colour, on top of the spin
+    pub timeline: Timeline,
+}
+
+/// Shapes drawn together in one pass, each keeping its own glyph and colour
+#[derive(Debug, Default, Clone)]
+pub struct Scene {
+    pub objects: Vec<Object>,
+    /// Keyframed distance from the camera to the centre of the scene
+    pub distance: Track<f128>,
+    /// Frames since the start, where the timelines are read
+    pub frame: f128,
+    /// Frames after which the timelines start again from the beginning
+    pub repeat: Option<f128>,
+    /// Where to look from, instead of the screen's own camera
+    pub camera: Option<Camera>,
+    /// How much to enlarge every object by, instead of fitting the scene to the screen
+    pub scale: Option<f128>,
+}
+
+impl Object {
+    /// A still object at the origin at its own size
+    pub fn new(model: impl Into<Model>) -> Object {
+        Object {
+            model: model.into(),
+            spin: [0.; 6],
+            scale: 1.,
+            position: Point([0., 0., 0.]),
+            timeline: Timeline::default(),
+        }
+    }
+
+    pub fn with_glyph(mut self, glyph: char) -> Object {
+        match &mut self.model {
+            Model::Mesh(mesh) => mesh.glyph = glyph,
+            Model::Mesh4d(mesh) => mesh.glyph = glyph,
+        }
+        self
+    }
+
+    pub fn with_colour(mut self, colour: Colour) -> Object {
+        match &mut self.model {
+            Model::Mesh(mesh) => mesh.colour = Some(colour),
+            Model::Mesh4d(mesh) => mesh.colour = Some(colour),
+        }
+        self
+    }
+
+    /// The object as it appears in the scene a number of frames into its timeline, projected
+    /// into 3d if it is 4d
+    pub fn mesh(&self, frame: f128) -> Mesh {
+        let orientation = self.timeline.orientation.sample(frame).unwrap_or_default();
+        let mut mesh = match &self.model {
+            Model::Mesh(mesh) => mesh.clone().transform(orientation.rotation_3d.to_matrix()),
+            Model::Mesh4d(mesh) => {
+                mesh.clone().transform(orientation.rotation_4d.to_matrix()).to_mesh_3d()
+            }
+        };
+        if let Some(colour) = self.timeline.colour.sample(frame) {
+            mesh.colour = Some(colour);
+        }
+        let scale = self.scale * self.timeline.scale.sample(frame).unwrap_or(1.);
+        mesh.map_vertices(|point| point * scale + self.position)
+    }
+
+    /// The object turned by an angle about the y and x axes, or in the yz and xw planes if it
+    /// is 4d, so that a whole turn brings it back to where it started
+    pub fn turned(&self, theta: f128) -> Object {
+        let model = match &self.model {
+            Model::Mesh(mesh) => {
+                Model::Mesh(mesh.clone().rotate_y_theta(theta).rotate_x_theta(theta))
+            }
+            Model::Mesh4d(mesh) => {
+                Model::Mesh4d(mesh.clone().rotate_xw_theta(theta).rotate_yz_theta(theta))
+            }
+        };
+        Object {
+            model,
+            ..self.clone()
+        }
+    }
+
+    /// Turns the object by one frame of its spin
+    pub fn step(&mut self) {
+        let [xy, xz, yz, xw, yw, zw] = self.spin;
+        self.model = match std::mem::replace(&mut self.model, Model::Mesh(Mesh::default())) {
+            Model::Mesh(mesh) => {
+                Model::Mesh(mesh.rotate_z_theta(xy).rotate_y_theta(xz).rotate_x_theta(yz))
+            }
+            // Each 4d rotation turns the plane its name leaves out, and the one turning xz goes
+            // the other way to `rotate_y_theta`
+            Model::Mesh4d(mesh) => Model::Mesh4d(
+                mesh.rotate_zw_theta(xy)
+                    .rotate_yw_theta(-xz)
+                    .rotate_xw_theta(yz)
+                    .rotate_yz_theta(xw)
+                    .rotate_xz_theta(yw)
+                    .rotate_xy_theta(zw),
+            ),
+        };
+    }
+}
+
+impl Scene {
+    pub fn with(mut self, object: Object) -> Scene {
+        self.objects.push(object);
+        self
+    }
+
+    /// Every object as it currently appears
+    pub fn meshes(&self) -> Vec<Mesh> {
+        self.objects.iter().map(|object| object.mesh(self.frame)).collect()
+    }
+
+    /// Every object joined into one mesh, drawn with the first object's glyph and colour
+    pub fn combined(&self) -> Mesh {
+        self.meshes()
+            .into_iter()
+            .reduce(|mut combined, mesh| {
+                let offset = combined.vertices.len();
+                combined.vertices.extend(mesh.vertices);
+                combined.edges.extend(mesh.edges.iter().map(|edge| edge.map(|i| i + offset)));
+                combined.faces.extend(
+                    mesh.faces
+                        .iter()
+                        .map(|face| face.iter().map(|i| i + offset).collect()),
+                );
+                combined
+            })
+            .unwrap_or_default()
+    }
+
+    /// Every object turned in place by the same angle
+    pub fn turned(&self, theta: f128) -> Scene {
+        Scene {
+            objects: self.objects.iter().map(|object| object.turned(theta)).collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Moves every object on by one frame
+    pub fn step(&mut self) {
+        self.objects.iter_mut().for_each(Object::step);
+        self.frame += 1.;
+        if self.repeat.is_some_and(|frames| self.frame >= frames) {
+            self.frame = 0.;
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{Object, Scene};
+    use crate::{
+        colour::Colour,
+        mesh::{fourd::Mesh as Mesh4d, Mesh, Model},
+        point::{fourd::Point as Point4d, Point},
+        shape::{fourd::Shape as Shape4d, Shape},
+        timeline::{Easing, Orientation, Timeline, Track},
+    };
+
+    #[test]
+    fn objects() {
+        let mut scene = Scene::default()
+            .with(Object::new(Shape::cube()).with_glyph('#'))
+            .with(Object {
+                spin: [0., 0., 0., 0., 0., std::f128::consts::FRAC_PI_2],
+                scale: 0.5,
+                position: Point([3., 0., 0.]),
+                ..Object::new(Shape4d::hypercube()).with_colour(Colour([255, 0, 0]))
+            });
+        let meshes = scene.meshes();
+        assert_eq!((meshes[0].glyph, meshes[0].colour), ('#', None));
+        assert_eq!(meshes[1].colour, Some(Colour([255, 0, 0])));
+        assert!(meshes[1].vertices.iter().all(|point| point[0] > 2.));
+        assert_eq!(scene.combined().edges.len(), 12 + 32);
+
+        // A quarter turn in zw leaves the hypercube looking the same
+        scene.step();
+        let keys = |mesh: &crate::mesh::Mesh| {
+            let mut keys = mesh.vertices.iter().map(Point::grid_key).collect::<Vec<_>>();
+            keys.sort();
+            keys
+        };
+        assert_eq!(keys(&scene.meshes()[1]), keys(&meshes[1]));
+    }
+
+    #[test]
+    fn spin_planes() {
+        let point = Point([1., 2., 3.]);
+        let point_4d = Point4d([1., 2., 3., 4.]);
+        for slot in 0..6 {
+            let mut spin = [0.; 6];
+            spin[slot] = 0.4;
+            let mut cube = Object {
+                spin,
+                ..Object::new(Mesh { vertices: vec![point], ..Mesh::default() })
+            };
+            let mut hypercube = Object {
+                spin,
+                ..Object::new(Mesh4d { vertices: vec![point_4d], ..Mesh4d::default() })
+            };
+            cube.step();
+            hypercube.step();
+            let (Model::Mesh(cube), Model::Mesh4d(hypercube)) = (cube.model, hypercube.model) else {
+                unreachable!();
+            };
+            let [x, y, z, w] = hypercube.vertices[0].0;
+            if slot < 3 {
+                assert!((cube.vertices[0] - Point([x, y, z])).l2_norm() < 1e-9);
+            } else {
+                assert_eq!(cube.vertices[0].0, point.0);
+            }
+            // Only the two axes of the named plane move
+            let [a, b] = [[0, 1], [0, 2], [1, 2], [0, 3], [1, 3], [2, 3]][slot];
+            for axis in (0..4).filter(|axis| ![a, b].contains(axis)) {
+                assert_eq!([x, y, z, w][axis], point_4d[axis]);
+            }
+        }
+    }
+
+    #[test]
+    fn timelines() {
+        let pi = std::f128::consts::PI;
+        let timeline = Timeline {
+            orientation: Track::default()
+                .key(0., Orientation::default(), Easing::Linear)
+                .key(10., Orientation::from_angles([pi / 2., 0., 0., 0., 0., 0.]), Easing::Linear),
+            scale: Track::default().key(10., 2., Easing::Linear),
+            colour: Track::default()
+                .key(0., Colour([0; 3]), Easing::Step)
+                .key(10., Colour([255; 3]), Easing::Step),
+        };
+        let mut scene = Scene {
+            repeat: Some(20.),
+            ..Scene::default().with(Object {
+                timeline,
+                ..Object::new(Shape::segment(1.))
+            })
+        };
+        let ends = |scene: &Scene| {
+            let mesh = &scene.meshes()[0];
+            (mesh.vertices[1] - mesh.vertices[0], mesh.colour)
+        };
+        let (line, colour) = ends(&scene);
+        assert!((line - Point([2., 0., 0.])).l2_norm() < 1e-9);
+        assert_eq!(colour, Some(Colour([0; 3])));
+
+        // Halfway through, the segment has turned an eighth of a turn from x towards y
+        (0..5).for_each(|_| scene.step());
+        let (line, colour) = ends(&scene);
+        let half = std::f128::consts::FRAC_1_SQRT_2 * 2.;
+        assert!((line - Point([half, half, 0.])).l2_norm() < 1e-9);
+        assert_eq!(colour, Some(Colour([0; 3])));
+
+        (0..5).for_each(|_| scene.step());
+        let (line, colour) = ends(&scene);
+        assert!((line - Point([0., 2., 0.])).l2_norm() < 1e-9);
+        assert_eq!(colour, Some(Colour([255; 3])));
+
+        (0..10).for_each(|_| scene.step());
+        assert_eq!(scene.frame, 0.);
+    }
+}
@@ -0,0 +1,489 @@
+use std::{
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use super::{Object, Scene};
+use crate::{
+    camera::{Camera, Projection},
+    colour::Colour,
+    format,
+    mesh::{is_glyph, Model},
+    point::Point,
+    screen::FRAME_TIME,
+    shape::{fourd::Shape as Shape4d, Shape},
+    timeline::{Easing, Orientation, Timeline, Track},
+    wythoff::{self, Diagram},
+};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The file is not valid TOML, or does not describe a scene
+    Syntax(toml::de::Error),
+    /// A `wythoff` shape that does not make a polytope
+    Wythoff(wythoff::Error),
+    /// A shape `file` that could not be loaded
+    Shape(PathBuf, format::Error),
+    /// A value out of range, such as a camera distance of 0
+    Invalid(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{err}"),
+            Error::Syntax(err) => write!(f, "{}", err.to_string().trim_end()),
+            Error::Wythoff(err) => write!(f, "{err}"),
+            Error::Shape(path, err) => write!(f, "{}: {err}", path.display()),
+            Error::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+/// A whole scene file. Angles are in degrees and times in seconds, unlike the radians and
+/// frames of `Scene`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    #[serde(default)]
+    camera: CameraSection,
+    /// Multiplies every object's size, instead of fitting the scene to the screen
+    scale: Option<f64>,
+    /// Seconds after which the keyframes start again
+    repeat: Option<f64>,
+    #[serde(default, rename = "object")]
+    objects: Vec<ObjectSection>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraSection {
+    distance: Option<f64>,
+    /// Degrees to swing around the scene from the default view, and then over it
+    yaw: Option<f64>,
+    pitch: Option<f64>,
+    /// Vertical field of view
+    fov: Option<f64>,
+    projection: Option<Projection>,
+    #[serde(default, rename = "keyframe")]
+    keyframes: Vec<CameraKey>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraKey {
+    time: f64,
+    distance: f64,
+    #[serde(default)]
+    easing: Easing,
+}
+
+/// One object, as a `[[object]]` table in a scene file or a map given to `add` in a script
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ObjectSection {
+    shape: Source,
+    position: Option<[f64; 3]>,
+    scale: Option<f64>,
+    /// Starting orientation, in the planes of `Object::spin`
+    rotation: Option<[f64; 6]>,
+    /// Degrees a second, in the planes of `Object::spin`
+    spin: Option<[f64; 6]>,
+    glyph: Option<char>,
+    colour: Option<Colour>,
+    #[serde(default, rename = "keyframe")]
+    keyframes: Vec<ObjectKey>,
+}
+
+/// Anything left out of a keyframe is not keyed there
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ObjectKey {
+    time: f64,
+    /// Turned from the starting orientation
+    rotation: Option<[f64; 6]>,
+    scale: Option<f64>,
+    colour: Option<Colour>,
+    #[serde(default)]
+    easing: Easing,
+}
+
+/// Where an object's shape comes from: one of the built-in shapes by name, a generated one
+/// with its sizes, a Wythoff construction, or a file
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) enum Source {
+    Cube,
+    Tetrahedron,
+    Octahedron,
+    Dodecahedron,
+    Icosahedron,
+    TruncatedTetrahedron,
+    Cuboctahedron,
+    TruncatedCube,
+    TruncatedOctahedron,
+    Rhombicuboctahedron,
+    TruncatedCuboctahedron,
+    Icosidodecahedron,
+    TruncatedIcosahedron,
+    Fivecell,
+    Hypercube,
+    Sixteencell,
+    Twentyfourcell,
+    Hundredtwentycell,
+    Sixhundredcell,
+    TetrahedralPrism,
+    DodecahedralPrism,
+    Polygon(usize),
+    Torus([usize; 2]),
+    Sphere([usize; 2]),
+    KleinBottle([usize; 2]),
+    MobiusStrip([usize; 2]),
+    Duoprism([usize; 2]),
+    CliffordTorus([usize; 2]),
+    HopfFibration([usize; 3]),
+    Wythoff(String),
+    File(PathBuf),
+}
+
+impl Source {
+    /// A generated shape's name, its sizes, and the least of each that still makes the shape
+    fn sizes(&self) -> Option<(&str, &[usize], &[usize])> {
+        Some(match self {
+            Source::Polygon(sides) => ("polygon", std::slice::from_ref(sides), &[3]),
+            Source::Torus(steps) => ("torus", steps, &[3, 3]),
+            Source::Sphere(steps) => ("sphere", steps, &[3, 2]),
+            Source::KleinBottle(steps) => ("klein-bottle", steps, &[3, 3]),
+            Source::MobiusStrip(steps) => ("mobius-strip", steps, &[3, 1]),
+            Source::Duoprism(sides) => ("duoprism", sides, &[3, 3]),
+            Source::CliffordTorus(steps) => ("clifford-torus", steps, &[3, 3]),
+            Source::HopfFibration(sizes) => ("hopf-fibration", sizes, &[1, 1, 3]),
+            _ => return None,
+        })
+    }
+
+    /// Builds the shape, reading files relative to `dir`
+    fn model(&self, dir: &Path) -> Result<Model, Error> {
+        if let Some((name, sizes, least)) = self.sizes() {
+            if sizes.iter().zip(least).any(|(size, least)| size < least) {
+                return Err(Error::Invalid(format!(
+                    "a {name} needs sizes of at least {least:?}, not {sizes:?}"
+                )));
+            }
+        }
+        Ok(match self {
+            Source::Cube => Shape::cube().into(),
+            Source::Tetrahedron => Shape::tetrahedron().into(),
+            Source::Octahedron => Shape::octahedron().into(),
+            Source::Dodecahedron => Shape::dodecahedron().into(),
+            Source::Icosahedron => Shape::icosahedron().into(),
+            Source::TruncatedTetrahedron => Shape::truncated_tetrahedron().into(),
+            Source::Cuboctahedron => Shape::cuboctahedron().into(),
+            Source::TruncatedCube => Shape::truncated_cube().into(),
+            Source::TruncatedOctahedron => Shape::truncated_octahedron().into(),
+            Source::Rhombicuboctahedron => Shape::rhombicuboctahedron().into(),
+            Source::TruncatedCuboctahedron => Shape::truncated_cuboctahedron().into(),
+            Source::Icosidodecahedron => Shape::icosidodecahedron().into(),
+            Source::TruncatedIcosahedron => Shape::truncated_icosahedron().into(),
+            Source::Fivecell => Shape4d::fivecell().into(),
+            Source::Hypercube => Shape4d::hypercube().into(),
+            Source::Sixteencell => Shape4d::sixteencell().into(),
+            Source::Twentyfourcell => Shape4d::twentyfourcell().into(),
+            Source::Hundredtwentycell => Shape4d::hundredtwentycell().into(),
+            Source::Sixhundredcell => Shape4d::sixhundredcell().into(),
+            Source::TetrahedralPrism => Shape4d::tetrahedral_prism().into(),
+            Source::DodecahedralPrism => Shape4d::dodecahedral_prism().into(),
+            Source::Polygon(sides) => Shape::polygon(*sides).into(),
+            Source::Torus([u, v]) => Shape::torus(*u, *v).into(),
+            Source::Sphere([u, v]) => Shape::sphere(*u, *v).into(),
+            Source::KleinBottle([u, v]) => Shape::klein_bottle(*u, *v).into(),
+            Source::MobiusStrip([u, v]) => Shape::mobius_strip(*u, *v).into(),
+            Source::Duoprism([p, q]) => Shape4d::duoprism(*p, *q).into(),
+            Source::CliffordTorus([u, v]) => Shape4d::clifford_torus(*u, *v).into(),
+            Source::HopfFibration([rings, fibres, steps]) => {
+                Shape4d::hopf_fibration(*rings, *fibres, *steps).into()
+            }
+            Source::Wythoff(diagram) => diagram
+                .parse::<Diagram>()
+                .and_then(|diagram| diagram.build())
+                .map_err(Error::Wythoff)?
+                .into(),
+            Source::File(path) => {
+                let path = dir.join(path);
+                format::load(&path).map_err(|err| Error::Shape(path, err))?
+            }
+        })
+    }
+}
+
+/// Frames in a second of animation
+pub(crate) fn frames_per_second() -> f128 {
+    1000. / FRAME_TIME.as_millis() as f128
+}
+
+pub(crate) fn angles(degrees: [f64; 6]) -> [f128; 6] {
+    degrees.map(|degrees| (degrees as f128).to_radians())
+}
+
+fn frame(seconds: f64) -> f128 {
+    seconds as f128 * frames_per_second()
+}
+
+impl ObjectSection {
+    /// Just a shape, given as its name or a generated shape's map
+    pub(crate) fn new(shape: Source) -> ObjectSection {
+        ObjectSection {
+            shape,
+            position: None,
+            scale: None,
+            rotation: None,
+            spin: None,
+            glyph: None,
+            colour: None,
+            keyframes: vec![],
+        }
+    }
+
+    pub(crate) fn object(&self, dir: &Path) -> Result<Object, Error> {
+        let orientation = Orientation::from_angles(angles(self.rotation.unwrap_or_default()));
+        let model = match self.shape.model(dir)? {
+            Model::Mesh(mesh) => Model::Mesh(mesh.transform(orientation.rotation_3d.to_matrix())),
+            Model::Mesh4d(mesh) => {
+                Model::Mesh4d(mesh.transform(orientation.rotation_4d.to_matrix()))
+            }
+        };
+        let mut object = Object::new(model);
+        if let Some(glyph) = self.glyph {
+            if !is_glyph(glyph) {
+                return Err(Error::Invalid(format!("{glyph:?} cannot be a glyph")));
+            }
+            object = object.with_glyph(glyph);
+        }
+        if let Some(colour) = self.colour {
+            object = object.with_colour(colour);
+        }
+        object.spin =
+            angles(self.spin.unwrap_or_default()).map(|theta| theta / frames_per_second());
+        object.scale = self.scale.unwrap_or(1.) as f128;
+        object.position = Point(self.position.unwrap_or_default().map(|x| x as f128));
+
+        let mut timeline = Timeline::default();
+        for key in &self.keyframes {
+            let time = frame(key.time);
+            if let Some(rotation) = key.rotation {
+                let orientation = Orientation::from_angles(angles(rotation));
+                timeline.orientation = timeline.orientation.key(time, orientation, key.easing);
+            }
+            if let Some(scale) = key.scale {
+                timeline.scale = timeline.scale.key(time, scale as f128, key.easing);
+            }
+            if let Some(colour) = key.colour {
+                timeline.colour = timeline.colour.key(time, colour, key.easing);
+            }
+        }
+        object.timeline = timeline;
+        Ok(object)
+    }
+}
+
+/// Fails unless the camera is some way in front of what it looks at
+pub(crate) fn check_distance(distance: f64) -> Result<(), Error> {
+    if distance > 0. && distance.is_finite() {
+        Ok(())
+    } else {
+        Err(Error::Invalid(format!(
+            "the camera distance must be more than 0, not {distance}"
+        )))
+    }
+}
+
+impl CameraSection {
+    fn camera(&self) -> Result<Camera, Error> {
+        let mut camera = Camera::default();
+        if let Some(fov) = self.fov {
+            if !(fov > 0. && fov < 180.) {
+                return Err(Error::Invalid(format!(
+                    "the field of view must be between 0 and 180 degrees, not {fov}"
+                )));
+            }
+            camera.fov = (fov as f128).to_radians();
+        }
+        if let Some(projection) = self.projection {
+            camera.projection = projection;
+        }
+        if let Some(distance) = self.distance {
+            check_distance(distance)?;
+            camera.dolly(distance as f128 / camera.distance());
+        }
+        let [yaw, pitch] =
+            [self.yaw, self.pitch].map(|angle| (angle.unwrap_or(0.) as f128).to_radians());
+        camera.orbit(yaw, pitch);
+        Ok(camera)
+    }
+}
+
+/// Reads a scene from TOML. Shape files are found relative to `dir`
+pub fn parse(text: &str, dir: &Path) -> Result<Scene, Error> {
+    let file: SceneFile = toml::from_str(text).map_err(Error::Syntax)?;
+    let objects = file
+        .objects
+        .iter()
+        .map(|object| object.object(dir))
+        .collect::<Result<_, _>>()?;
+    let mut distance = Track::default();
+    for key in &file.camera.keyframes {
+        check_distance(key.distance)?;
+        distance = distance.key(frame(key.time), key.distance as f128, key.easing);
+    }
+    Ok(Scene {
+        objects,
+        distance,
+        repeat: file.repeat.map(frame),
+        camera: Some(file.camera.camera()?),
+        scale: file.scale.map(|scale| scale as f128),
+        ..Scene::default()
+    })
+}
+
+/// Reads a scene file. Its shape files are found relative to the scene file
+pub fn load(path: &Path) -> Result<Scene, Error> {
+    let text = fs::read_to_string(path)?;
+    parse(&text, path.parent().unwrap_or(Path::new(".")))
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::path::Path;
+
+    use super::{parse, Error};
+    use crate::{camera::Projection, colour::Colour, mesh::Model};
+
+    #[test]
+    fn scenes() {
+        let scene = parse(
+            r##"
+            scale = 2
+            repeat = 4
+
+            [camera]
+            distance = 6
+            fov = 90
+            projection = "orthographic"
+
+            [[camera.keyframe]]
+            time = 1
+            distance = 3
+
+            [[object]]
+            shape = { duoprism = [3, 4] }
+            position = [1, 2, 3]
+            spin = [0, 0, 0, 0, 0, 20]
+            glyph = "+"
+            colour = "#ff0000"
+
+            [[object.keyframe]]
+            time = 0.5
+            scale = 2
+            easing = "ease-in"
+
+            [[object]]
+            shape = "cube"
+            rotation = [90, 0, 0, 0, 0, 0]
+            "##,
+            Path::new("."),
+        )
+        .unwrap();
+        assert_eq!((scene.scale, scene.repeat), (Some(2.), Some(80.)));
+        let camera = scene.camera.unwrap();
+        assert_eq!(camera.projection, Projection::Orthographic);
+        assert!((camera.distance() - 6.).abs() < 1e-9);
+        assert_eq!(scene.distance.sample(20.), Some(3.));
+
+        let [duoprism, cube] = &scene.objects[..] else {
+            panic!("expected two objects");
+        };
+        let Model::Mesh4d(mesh) = &duoprism.model else {
+            panic!("a duoprism is 4d");
+        };
+        assert_eq!(
+            (mesh.vertices.len(), mesh.glyph, mesh.colour),
+            (12, '+', Some(Colour([255, 0, 0])))
+        );
+        // 20 degrees a second is one degree a frame
+        assert!((duoprism.spin[5] - 1f128.to_radians()).abs() < 1e-9);
+        assert_eq!(duoprism.timeline.scale.0[0].frame, 10.);
+        let Model::Mesh(mesh) = &cube.model else {
+            panic!("a cube is 3d");
+        };
+        // A quarter turn leaves the cube's corners where they were
+        let corners = |vertices: &[crate::point::Point]| {
+            let mut keys = vertices
+                .iter()
+                .map(|point| point.grid_key())
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys
+        };
+        let unturned = crate::mesh::Mesh::from(crate::shape::Shape::cube());
+        assert_eq!(corners(&mesh.vertices), corners(&unturned.vertices));
+
+        for text in [
+            include_str!("../../scenes/demo.toml"),
+            include_str!("../../scenes/choreography.toml"),
+        ] {
+            assert!(!parse(text, Path::new(".")).unwrap().objects.is_empty());
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text| parse(text, Path::new(".")).unwrap_err();
+        assert!(matches!(
+            error("[[object]]\nshape = \"cubee\""),
+            Error::Syntax(_)
+        ));
+        assert!(matches!(
+            error("[[object]]\nshape = \"cube\"\nsize = 2"),
+            Error::Syntax(_)
+        ));
+        assert!(matches!(
+            error("[[object]]\nshape = \"cube\"\ncolour = \"red\""),
+            Error::Syntax(_)
+        ));
+        assert!(matches!(
+            error("[[object]]\nshape = { wythoff = \"{4,3\" }"),
+            Error::Wythoff(_)
+        ));
+        assert!(matches!(
+            error("[[object]]\nshape = { file = \"nowhere.obj\" }"),
+            Error::Shape(..)
+        ));
+        for text in [
+            "[camera]\ndistance = 0",
+            "[camera]\ndistance = -2",
+            "[[camera.keyframe]]\ntime = 1\ndistance = 0",
+            "[camera]\nfov = 0",
+            "[camera]\nfov = 180",
+            "[[object]]\nshape = { polygon = 0 }",
+            "[[object]]\nshape = { torus = [0, 0] }",
+            "[[object]]\nshape = { duoprism = [0, 4] }",
+            "[[object]]\nshape = { hopf-fibration = [1, 0, 0] }",
+            "[[object]]\nshape = \"cube\"\nglyph = \"\\n\"",
+            "[[object]]\nshape = \"cube\"\nglyph = \" \"",
+        ] {
+            assert!(matches!(error(text), Error::Invalid(_)), "{text}");
+        }
+    }
+}
@@ -1,55 +1,84 @@
-use std::io::{stdin, stdout, Stdout, Write};
+use std::io::{self, stdin, stdout, Write};
+use std::iter::successors;
 use std::sync::mpsc::TryRecvError;
 use std::thread;
 use std::{char, fmt::Display, thread::sleep, time::Duration};
 
+use rayon::prelude::*;
+use termion::color::{Fg, Reset, Rgb};
 use termion::event::Key;
 use termion::input::TermRead;
 use termion::raw::IntoRawMode;
 
+use crate::camera::{Camera, Projection};
+use crate::cast::Recorder;
+use crate::colour::Colour;
+use crate::config::Config;
+use crate::matrix::fourd::Matrix4x4;
 use crate::shape::fourd::Shape as Shape4d;
+use crate::line::fourd::Line as Line4d;
+use crate::mesh::{fourd::Mesh as Mesh4d, Mesh, VERTICES_PER_TASK};
+use crate::point::fourd::Point as Point4d;
+use crate::scene::Scene;
+use crate::script::Script;
 use crate::{line::Line, point::Point, shape::Shape};
+use clip::Rect;
+
+pub mod clip;
+pub mod raster;
+pub mod svg;
 
 const STACK_SIZE: usize = 4 * 1024 * 1024;
+/// Time between frames of the automatic modes
+pub const FRAME_TIME: Duration = Duration::from_millis(50);
+/// Fraction of the screen that auto-fitted shapes fill
+const FIT_MARGIN: f128 = 0.9;
+/// Fewest lines a thread rasterises at once
+const LINES_PER_TASK: usize = 16;
 pub type ScreenBuffer = [[char; 78]; 42];
 pub struct MyScreenBuffer<const N: usize, const M: usize>([[char; N]; M]);
 
-#[derive(Debug, Clone, Copy)]
-pub struct Screen<const N: usize, const M: usize> {
-    screen: [[char; N]; M],
+/// An edge projected into an image, with its depth from 0 at the near plane to 1 at the far
+/// plane, for the outputs that draw real lines
+struct Segment {
+    start: (f128, f128),
+    end: (f128, f128),
+    depth: f128,
+    colour: Option<Colour>,
 }
 
-impl<const N: usize, const M: usize> Screen<N, M> {
-    pub fn init_render_fivecell(mut self) -> ! {
-        let mut fivecell = Shape4d::fivecell() * 3.5;
-        fivecell.1 = '.';
-        //let mut fivecell2 = Shape4d::fivecell();
-        //fivecell = fivecell.clone().rotate_xy_theta(std::f128::consts::PI / 6.);
-        loop {
-            let cube = fivecell.to_shape_3d();
-            self.render_shape(cube);
-            //let cube2 = fivecell2.to_shape_3d();
-            //self.render_shape(cube2);
-            self.print_screen();
-            self.clear_screen();
-
-            //fivecell = fivecell.rotate_xw_theta(std::f128::consts::PI / 70.);
-            //fivecell = fivecell.rotate_yz_theta(std::f128::consts::PI / 90.);
+/// The cells of the screen covered by the lines one thread has drawn. Every line drawn
+/// together shares a glyph and colour, so tiles can be merged in any order
+struct Tile<const N: usize, const M: usize>(Vec<bool>);
 
-            //fivecell = fivecell.rotate_yw_theta(std::f128::consts::PI / 70.);
-            //fivecell = fivecell.rotate_xz_theta(std::f128::consts::PI / 90.);
-
-            fivecell = fivecell.rotate_zw_theta(std::f128::consts::PI / 90.);
-            //fivecell = fivecell.clone().rotate_xz_theta(std::f128::consts::PI / 90.);
-            fivecell = fivecell.rotate_xy_theta(std::f128::consts::PI / 90.);
+impl<const N: usize, const M: usize> Tile<N, M> {
+    fn new() -> Self {
+        Tile(vec![false; N * M])
+    }
 
-            //fivecell2 = fivecell2.rotate_zw_theta(std::f128::consts::PI / -90.);
-            //fivecell2 = fivecell2.rotate_xy_theta(std::f128::consts::PI / -90.);
+    /// Marks a point given in buffer coordinates. The point must already be within the viewport
+    fn set_point(&mut self, (x, y): (f128, f128)) {
+        let (x, y) = (x.round() as usize, y.round() as usize);
+        self.0[y * N + x] = true;
+    }
 
-            sleep(Duration::from_millis(50));
-        }
+    fn merge(mut self, other: Self) -> Self {
+        self.0.iter_mut().zip(other.0).for_each(|(cell, other)| *cell |= other);
+        self
     }
+}
 
+#[derive(Debug, Clone, Copy)]
+pub struct Screen<const N: usize, const M: usize> {
+    screen: [[char; N]; M],
+    /// Colour of each character in `screen`, or `None` for the terminal's own
+    colours: [[Option<Colour>; N]; M],
+    pub camera: Camera,
+    /// Height of a terminal character cell divided by its width
+    pub char_ratio: f128,
+}
+
+impl<const N: usize, const M: usize> Screen<N, M> {
     pub fn init_render_hypercube_manual(mut self) {
         let stdin = stdin();
         let mut stdout = stdout().into_raw_mode().unwrap();
@@ -62,8 +91,9 @@ impl<const N: usize, const M: usize> Screen<N, M> {
         ).unwrap();
         stdout.flush().unwrap();
 
-        let mut hypercube = Shape4d::hypercube() * 1.5;
-        hypercube.1 = '.';
+        let mut hypercube = Mesh4d::from(Shape4d::hypercube());
+        hypercube.glyph = '.';
+        let scale = self.fit_scale(&Self::poses_4d(&hypercube));
 
         let ref_stdout = &mut stdout;
 
@@ -77,9 +107,11 @@ impl<const N: usize, const M: usize> Screen<N, M> {
                             tx.send(Some(Key::Char('q'))).unwrap();
                             break;
                         }
-                        key @ Key::Char('h' | 'j' | 'k' | 'l' | 'i' | 'n') => {
-                            tx.send(Some(*key)).unwrap()
-                        }
+                        key @ (Key::Char('h' | 'j' | 'k' | 'l' | 'i' | 'n' | '+' | '-' | 'o')
+                        | Key::Left
+                        | Key::Right
+                        | Key::Up
+                        | Key::Down) => tx.send(Some(*key)).unwrap(),
                         _ => (),
                     }
                 }
@@ -93,11 +125,18 @@ impl<const N: usize, const M: usize> Screen<N, M> {
                     XZ,
                     XY,
                 }
-                let cube = hypercube.to_shape_3d();
-                self.render_shape(cube);
+                enum Message {
+                    Rotate(Rotate),
+                    Orbit(f128, f128),
+                    Dolly(f128),
+                    ToggleProjection,
+                }
+                let cube = hypercube.to_mesh_3d() * scale;
+                self.render_mesh(&cube);
                 self.print_screen_raw(ref_stdout);
                 self.clear_screen();
 
+                let step = std::f128::consts::PI / 90.;
                 loop {
                     let message = match rx.try_recv() {
                         Err(TryRecvError::Disconnected) => break,
@@ -106,53 +145,58 @@ impl<const N: usize, const M: usize> Screen<N, M> {
                         },
                         Ok(Some(Key::Char(char @ ('h' | 'j' | 'k' | 'l' | 'i' | 'n')))) => {
                             match char {
-                                'h' => Some(Rotate::XY),
-                                'j' => Some(Rotate::XZ),
-                                'k' => Some(Rotate::YZ),
-                                'l' => Some(Rotate::ZW),
-                                'i' => Some(Rotate::YW),
-                                'n' => Some(Rotate::XW),
+                                'h' => Some(Message::Rotate(Rotate::XY)),
+                                'j' => Some(Message::Rotate(Rotate::XZ)),
+                                'k' => Some(Message::Rotate(Rotate::YZ)),
+                                'l' => Some(Message::Rotate(Rotate::ZW)),
+                                'i' => Some(Message::Rotate(Rotate::YW)),
+                                'n' => Some(Message::Rotate(Rotate::XW)),
                                 _ => todo!(),
                             }
                         }
+                        Ok(Some(Key::Left)) => Some(Message::Orbit(-step, 0.)),
+                        Ok(Some(Key::Right)) => Some(Message::Orbit(step, 0.)),
+                        Ok(Some(Key::Up)) => Some(Message::Orbit(0., step)),
+                        Ok(Some(Key::Down)) => Some(Message::Orbit(0., -step)),
+                        Ok(Some(Key::Char('+'))) => Some(Message::Dolly(0.95)),
+                        Ok(Some(Key::Char('-'))) => Some(Message::Dolly(1. / 0.95)),
+                        Ok(Some(Key::Char('o'))) => Some(Message::ToggleProjection),
                         Ok(None) => None,
                         _ => None,
                     };
 
-                    if message.is_none() {
+                    let Some(message) = message else {
                         continue;
-                    }
-
-                    let cube = hypercube.to_shape_3d();
-                    self.render_shape(cube);
-                    self.print_screen_raw(ref_stdout);
-                    self.clear_screen();
-
-
-                    //hypercube = hypercube.rotate_zw_theta(std::f128::consts::PI / 90.);
-                    //hypercube = hypercube.clone().rotate_xz_theta(std::f128::consts::PI / 90.);
-                    //hypercube = hypercube.rotate_xy_theta(std::f128::consts::PI / 90.);
+                    };
 
-                    match message.unwrap() {
-                        Rotate::ZW => {
-                            hypercube = hypercube.rotate_zw_theta(std::f128::consts::PI / 90.);
+                    match message {
+                        Message::Rotate(Rotate::ZW) => {
+                            hypercube = hypercube.rotate_zw_theta(step);
                         }
-                        Rotate::YW => {
-                            hypercube = hypercube.rotate_yw_theta(std::f128::consts::PI / 90.);
+                        Message::Rotate(Rotate::YW) => {
+                            hypercube = hypercube.rotate_yw_theta(step);
                         }
-                        Rotate::YZ => {
-                            hypercube = hypercube.rotate_yz_theta(std::f128::consts::PI / 90.);
+                        Message::Rotate(Rotate::YZ) => {
+                            hypercube = hypercube.rotate_yz_theta(step);
                         }
-                        Rotate::XW => {
-                            hypercube = hypercube.rotate_xw_theta(std::f128::consts::PI / 90.);
+                        Message::Rotate(Rotate::XW) => {
+                            hypercube = hypercube.rotate_xw_theta(step);
                         }
-                        Rotate::XZ => {
-                            hypercube = hypercube.rotate_xz_theta(std::f128::consts::PI / 90.);
+                        Message::Rotate(Rotate::XZ) => {
+                            hypercube = hypercube.rotate_xz_theta(step);
                         }
-                        Rotate::XY => {
-                            hypercube = hypercube.rotate_xy_theta(std::f128::consts::PI / 90.);
+                        Message::Rotate(Rotate::XY) => {
+                            hypercube = hypercube.rotate_xy_theta(step);
                         }
+                        Message::Orbit(yaw, pitch) => self.camera.orbit(yaw, pitch),
+                        Message::Dolly(factor) => self.camera.dolly(factor),
+                        Message::ToggleProjection => self.camera.toggle_projection(),
                     }
+
+                    let cube = hypercube.to_mesh_3d() * scale;
+                    self.render_mesh(&cube);
+                    self.print_screen_raw(ref_stdout);
+                    self.clear_screen();
                 }
             }).unwrap();
         });
@@ -160,127 +204,539 @@ impl<const N: usize, const M: usize> Screen<N, M> {
         write!(stdout, "{}{}{}", termion::clear::All, termion::cursor::Goto(1, 1), termion::cursor::Show).unwrap();
     }
 
-    pub fn init_render_hypercube(mut self) -> ! {
-        let mut hypercube = Shape4d::hypercube() * 1.5;
-        hypercube.1 = '.';
-        //let mut hypercube2 = Shape4d::hypercube();
-        //hypercube = hypercube.clone().rotate_xy_theta(std::f128::consts::PI / 6.);
+    /// Animates every object in a scene together forever, scaled so they all stay on screen
+    pub fn init_render_scene(mut self, mut scene: Scene) -> ! {
+        let scale = self.scene_scale(&scene);
         loop {
-            let cube = hypercube.to_shape_3d();
-            self.render_shape(cube);
-            //let cube2 = hypercube2.to_shape_3d();
-            //self.render_shape(cube2);
+            self.render_scene(&scene, scale);
             self.print_screen();
             self.clear_screen();
+            scene.step();
+            sleep(FRAME_TIME);
+        }
+    }
 
-            //hypercube = hypercube.rotate_xw_theta(std::f128::consts::PI / 70.);
-            //hypercube = hypercube.rotate_yz_theta(std::f128::consts::PI / 90.);
+    /// Animates a scene for a number of frames into an asciinema recording, as quickly as it
+    /// can be drawn rather than in real time
+    pub fn record_scene<W: Write>(
+        mut self,
+        mut scene: Scene,
+        frames: usize,
+        recorder: &mut Recorder<W>,
+    ) -> io::Result<()> {
+        let scale = self.scene_scale(&scene);
+        for frame in 0..frames {
+            self.render_scene(&scene, scale);
+            self.print_screen_raw(recorder);
+            self.clear_screen();
+            recorder.frame(FRAME_TIME * frame as u32)?;
+            scene.step();
+        }
+        Ok(())
+    }
 
-            //hypercube = hypercube.rotate_yw_theta(std::f128::consts::PI / 70.);
-            //hypercube = hypercube.rotate_xz_theta(std::f128::consts::PI / 90.);
+    /// Animates a scene for a number of frames, giving each frame as plain text as it is drawn
+    pub fn text_frames(mut self, mut scene: Scene, frames: usize) -> impl Iterator<Item = String> {
+        let scale = self.scene_scale(&scene);
+        (0..frames).map(move |_| {
+            let text = self.render_to_string(&scene, scale);
+            scene.step();
+            text
+        })
+    }
 
-            hypercube = hypercube.rotate_zw_theta(std::f128::consts::PI / 90.);
-            //hypercube = hypercube.clone().rotate_xz_theta(std::f128::consts::PI / 90.);
-            hypercube = hypercube.rotate_xy_theta(std::f128::consts::PI / 90.);
+    /// Scale factor that keeps a scene on screen, seen from its camera, over the first few
+    /// seconds of its animation. Each pose is fitted from where the camera is at the time, so
+    /// a camera that moves in does not push the scene off screen. A scene with a scale of its
+    /// own keeps it
+    pub fn scene_scale(&self, scene: &Scene) -> f128 {
+        if let Some(scale) = scene.scale {
+            return scale;
+        }
+        successors(Some(scene.clone()), |scene| {
+            let mut scene = scene.clone();
+            scene.step();
+            Some(scene)
+        }).step_by(6).take(30).map(|scene| {
+            let mut screen = *self;
+            screen.place_camera(&scene);
+            screen.fit_scale(&[scene.combined()])
+        }).fold(f128::INFINITY, f128::min)
+    }
 
-            //hypercube2 = hypercube2.rotate_zw_theta(std::f128::consts::PI / -90.);
-            //hypercube2 = hypercube2.rotate_xy_theta(std::f128::consts::PI / -90.);
+    /// Lets the user measure the aspect ratio of their terminal font by stretching a square
+    /// until it looks square, then saves it to the config file
+    pub fn init_calibrate(mut self) {
+        let stdin = stdin();
+        let mut stdout = stdout().into_raw_mode().unwrap();
+        write!(stdout, "{}", termion::cursor::Hide).unwrap();
+
+        self.camera.projection = Projection::Orthographic;
+        let square = Mesh::from(Shape(vec![
+            Line([Point([-1., 0., -1.]), Point([ 1., 0., -1.])]),
+            Line([Point([ 1., 0., -1.]), Point([ 1., 0.,  1.])]),
+            Line([Point([ 1., 0.,  1.]), Point([-1., 0.,  1.])]),
+            Line([Point([-1., 0.,  1.]), Point([-1., 0., -1.])]),
+        ], '#'));
+        let scale = self.fit_scale(std::slice::from_ref(&square));
+        let square = square * scale;
+
+        let mut keys = stdin.keys();
+        loop {
+            self.render_mesh(&square);
+            write!(stdout, "{}{}", termion::clear::All, termion::cursor::Goto(1, 1)).unwrap();
+            self.print_screen_raw(&mut stdout);
+            write!(
+                stdout,
+                "Character ratio {:.3}: h/l or left/right until the square is square, enter saves, q cancels\r\n",
+                self.char_ratio as f64,
+            ).unwrap();
+            stdout.flush().unwrap();
+            self.clear_screen();
 
-            sleep(Duration::from_millis(50));
+            match keys.next() {
+                Some(Ok(Key::Char('h') | Key::Left)) => self.char_ratio /= 1.01,
+                Some(Ok(Key::Char('l') | Key::Right)) => self.char_ratio *= 1.01,
+                Some(Ok(Key::Char('\n'))) => {
+                    let config = Config { char_ratio: self.char_ratio };
+                    if let Err(err) = config.save() {
+                        write!(stdout, "Could not save config: {err}\r\n").unwrap();
+                    }
+                    break;
+                }
+                Some(Ok(Key::Char('q') | Key::Ctrl('c'))) | None => break,
+                _ => (),
+            }
         }
+
+        write!(stdout, "{}", termion::cursor::Show).unwrap();
     }
 
-    pub fn init_render_cube(mut self) -> ! {
-        let mut cube = Shape::cube() * 1.1;
-        //let mut small_cube = cube.clone() * 0.6;
-        cube.1 = '.';
-        //small_cube.1 = '*';
+    /// Shows the 3d cross-sections of a convex 4d shape, sweeping the hyperplane w = c through
+    /// it with w/s or up/down. hjklin rotate the shape, which tilts the hyperplane relative to
+    /// it, and left/right orbit the camera
+    pub fn init_render_slice(mut self, shape: Shape4d) {
+        let Some(mut mesh) = Mesh4d::convex_hull(&shape.vertices()) else {
+            eprintln!("Only shapes with some 4d volume can be sliced");
+            return;
+        };
+        mesh.glyph = '.';
+        let radius = mesh.vertices.iter().map(Point4d::l2_norm).fold(0., f128::max);
+        // Every slice fits inside the sphere around the shape
+        let scale = self.fit_scale(&[Mesh::from(Shape::sphere(24, 12) * radius)]);
+
+        let stdin = stdin();
+        let mut stdout = stdout().into_raw_mode().unwrap();
+        write!(stdout, "{}", termion::cursor::Hide).unwrap();
+
+        let normal = Point4d([0., 0., 0., 1.]);
+        let sweep = radius / 30.;
+        let step = std::f128::consts::PI / 90.;
+        let mut offset = 0.;
+        let mut keys = stdin.keys();
         loop {
-            //self.render_shape(small_cube.clone());
-            self.render_shape(cube.clone());
-            self.print_screen();
+            self.render_mesh(&(mesh.slice(normal, offset) * scale));
+            write!(stdout, "{}{}", termion::clear::All, termion::cursor::Goto(1, 1)).unwrap();
+            self.print_screen_raw(&mut stdout);
+            write!(
+                stdout,
+                "w = {:+.3}: w/s or up/down sweep, hjklin rotate, left/right orbit, q quits\r\n",
+                offset as f64,
+            ).unwrap();
+            stdout.flush().unwrap();
             self.clear_screen();
-            // small_cube = small_cube.clone().rotate_y_theta(std::f128::consts::PI / 90.);
-            // small_cube = small_cube.clone().rotate_x_theta(std::f128::consts::PI / 70.);
-            // small_cube = small_cube.clone().rotate_z_theta(std::f128::consts::PI / 180.);
-
-            // cube = cube.clone().rotate_y_theta(std::f128::consts::PI / (2. * -90.));
-            // cube = cube.clone().rotate_x_theta(std::f128::consts::PI / (2. * -70.));
-            // cube = cube.clone().rotate_z_theta(std::f128::consts::PI / (2. * -180.));
-
-            cube = cube.clone().rotate_y_theta(std::f128::consts::PI / 90.);
-            cube = cube.clone().rotate_x_theta(std::f128::consts::PI / 70.);
-            cube = cube.clone().rotate_z_theta(std::f128::consts::PI / 180.);
-
-            //small_cube = small_cube.clone().rotate_y_theta(std::f128::consts::PI / (2. * -90.));
-            //small_cube = small_cube.clone().rotate_x_theta(std::f128::consts::PI / (2. * -70.));
-            //small_cube = small_cube.clone().rotate_z_theta(std::f128::consts::PI / (2. * -180.));
-            sleep(Duration::from_millis(50));
+
+            match keys.next() {
+                Some(Ok(Key::Char('w') | Key::Up)) => offset = (offset + sweep).min(radius),
+                Some(Ok(Key::Char('s') | Key::Down)) => offset = (offset - sweep).max(-radius),
+                Some(Ok(Key::Char('h'))) => mesh = mesh.rotate_xy_theta(step),
+                Some(Ok(Key::Char('j'))) => mesh = mesh.rotate_xz_theta(step),
+                Some(Ok(Key::Char('k'))) => mesh = mesh.rotate_yz_theta(step),
+                Some(Ok(Key::Char('l'))) => mesh = mesh.rotate_zw_theta(step),
+                Some(Ok(Key::Char('i'))) => mesh = mesh.rotate_yw_theta(step),
+                Some(Ok(Key::Char('n'))) => mesh = mesh.rotate_xw_theta(step),
+                Some(Ok(Key::Left)) => self.camera.orbit(-step, 0.),
+                Some(Ok(Key::Right)) => self.camera.orbit(step, 0.),
+                Some(Ok(Key::Char('q') | Key::Ctrl('c'))) | None => break,
+                _ => (),
+            }
         }
+
+        write!(stdout, "{}", termion::cursor::Show).unwrap();
     }
 
-    fn render_shape(&mut self, shape: Shape) {
-        shape.0.iter().for_each(|line| {
-            self.render_line(*line, shape.1);
+    /// Runs a script's scene until q is pressed, passing it the other keys. If the script
+    /// fails, its error stays on screen until then
+    pub fn init_render_script(mut self, mut script: Script) {
+        let stdin = stdin();
+        let mut stdout = stdout().into_raw_mode().unwrap();
+        write!(stdout, "{}", termion::cursor::Hide).unwrap();
+
+        let (tx, rx) = std::sync::mpmc::channel::<Key>();
+        thread::scope(|s| {
+            thread::Builder::new().stack_size(STACK_SIZE).spawn_scoped(s, move || {
+                for key in stdin.keys().map_while(Result::ok) {
+                    let quit = matches!(key, Key::Char('q') | Key::Ctrl('c'));
+                    if tx.send(key).is_err() || quit {
+                        break;
+                    }
+                }
+            }).unwrap();
+
+            let mut failure = None;
+            // The scene is fitted to the screen again whenever objects are added
+            let mut fitted = (usize::MAX, 1.);
+            'frames: loop {
+                loop {
+                    let key = match rx.try_recv() {
+                        Ok(Key::Char('q') | Key::Ctrl('c')) | Err(TryRecvError::Disconnected) => break 'frames,
+                        Ok(key) => key,
+                        Err(TryRecvError::Empty) => break,
+                    };
+                    if let (None, Some(name)) = (&failure, key_name(key)) {
+                        failure = script.key(&name).err();
+                    }
+                }
+                if failure.is_none() {
+                    failure = script.frame().err();
+                }
+
+                let scene = script.scene();
+                if fitted.0 != scene.objects.len() {
+                    fitted = (scene.objects.len(), self.scene_scale(&scene));
+                }
+                self.render_scene(&scene, fitted.1);
+                for text in script.text() {
+                    self.draw_text(text.row, text.column, &text.text);
+                }
+                write!(stdout, "{}{}", termion::clear::All, termion::cursor::Goto(1, 1)).unwrap();
+                self.print_screen_raw(&mut stdout);
+                match &failure {
+                    Some(err) => write!(stdout, "{err}, q quits\r\n"),
+                    None => write!(stdout, "{}\r\n", printable(&script.status())),
+                }.unwrap();
+                stdout.flush().unwrap();
+                self.clear_screen();
+                sleep(FRAME_TIME);
+            }
         });
+
+        write!(stdout, "{}", termion::cursor::Show).unwrap();
     }
 
-    fn render_line(&mut self, line: Line, char: char) {
-        line.find_78_points_in_between()
-            .iter()
-            .for_each(|point| self.set_point(*point, char));
-    }
-
-    // usually 42 for M and 78 for N
-    fn set_point(&mut self, coords: Point, char: char) {
-        let (screen_x, screen_y) = coords.to_screen_xy();
-        // let (buff_coord_x, buff_coord_y) = (
-        //     ((screen_x * 39. / 2.16666666666666 * (5. / 3.)) + 39.).round() as usize,
-        //     ((screen_y * 21. / 1.16666666666666) + 21.).round() as usize,
-        //     //((screen_x * 39. / 3.25 * (5. / 3.)) + 39.).round() as usize,
-        //     //((screen_y * 21. / 1.75) + 21.).round() as usize,
-        // );
-        let n = N as f128 / 2.;
-        let m = M as f128 / 2.;
-        let char_ratio = 5. / 3.;
-        let n_m = N as f128 / M as f128;
-        let (buff_coord_x, buff_coord_y) = (
-            ((screen_x * n / (n_m * 1.1666666666) * char_ratio) + n).round() as usize,
-            ((screen_y * m / 1.16666666666666) + m).round() as usize,
-            //((screen_x * 39. / 3.25 * (5. / 3.)) + 39.).round() as usize,
-            //((screen_y * 21. / 1.75) + 21.).round() as usize,
-        );
-        if let Some(x) = self.screen.get_mut(buff_coord_y) {
-            if let Some(coord) = x.get_mut(buff_coord_x) {
-                *coord = char;
+    /// Poses of a 4d shape over a full turn of the rotation used by the automatic modes,
+    /// projected into 3d
+    fn poses_4d(shape: &Mesh4d) -> Vec<Mesh> {
+        successors(Some(shape.clone()), |shape| {
+            Some(shape.clone()
+                .rotate_zw_theta(std::f128::consts::PI / 90.)
+                .rotate_xy_theta(std::f128::consts::PI / 90.))
+        }).step_by(6).take(30).map(|shape| shape.to_mesh_3d()).collect()
+    }
+
+    /// Bounding box of the shape on screen, in normalized device coordinates
+    pub fn projected_bounds(&self, mesh: &Mesh) -> Option<Rect> {
+        let view_projection = self.camera.view_projection(self.aspect());
+        mesh.lines()
+            .filter_map(|line| Camera::clip_line(&view_projection, line))
+            .flat_map(|line| line.0)
+            .map(Camera::perspective_divide)
+            .fold(None, |bounds: Option<Rect>, (x, y)| match bounds {
+                None => Some(Rect { min: (x, y), max: (x, y) }),
+                Some(Rect { min, max }) => Some(Rect {
+                    min: (min.0.min(x), min.1.min(y)),
+                    max: (max.0.max(x), max.1.max(y)),
+                }),
+            })
+    }
+
+    /// Scale factor that makes the largest of the given poses of a shape just fit on screen
+    pub fn fit_scale(&self, poses: &[Mesh]) -> f128 {
+        // How far the scaled poses reach from the centre of the screen, where 1 is the border
+        let extent = |scale: f128| {
+            poses.iter()
+                .filter_map(|pose| self.projected_bounds(&(pose.clone() * scale)))
+                .map(|Rect { min, max }| min.0.abs().max(min.1.abs()).max(max.0.abs()).max(max.1.abs()))
+                .fold(0., f128::max)
+        };
+        let (mut low, mut high) = (0., 1.);
+        while extent(high) < FIT_MARGIN && high < 1e6 {
+            (low, high) = (high, high * 2.);
+        }
+        for _ in 0..32 {
+            let mid = (low + high) / 2.;
+            if extent(mid) < FIT_MARGIN {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    /// Draws a mesh, transforming each vertex into clip coordinates only once
+    pub fn render_mesh(&mut self, mesh: &Mesh) {
+        let view_projection = self.camera.view_projection(self.aspect());
+        let clip = Self::clip_vertices(&view_projection, mesh);
+        let lines = mesh.edges
+            .par_iter()
+            .map(|[a, b]| Camera::clip_near(Line4d([clip[*a], clip[*b]])));
+        self.draw_lines(lines, mesh.glyph, mesh.colour);
+    }
+
+    /// The vertices of a mesh in clip coordinates, transformed across the thread pool
+    fn clip_vertices(view_projection: &Matrix4x4, mesh: &Mesh) -> Vec<Point4d> {
+        mesh.vertices
+            .par_iter()
+            .with_min_len(VERTICES_PER_TASK)
+            .map(|point| *view_projection * point.homogeneous())
+            .collect()
+    }
+
+    /// Draws every object in a scene, scaled about the origin
+    pub fn render_scene(&mut self, scene: &Scene, scale: f128) {
+        self.place_camera(scene);
+        for mesh in scene.meshes() {
+            self.render_mesh(&(mesh * scale));
+        }
+    }
+
+    /// Uses the scene's camera, if it has one, at the distance its timeline gives at its
+    /// current frame, if it gives one
+    pub fn place_camera(&mut self, scene: &Scene) {
+        if let Some(camera) = scene.camera {
+            self.camera = camera;
+        }
+        if let Some(distance) = scene.distance.sample(scene.frame) {
+            self.camera.dolly(distance / self.camera.distance());
+        }
+    }
+
+    pub fn render_shape(&mut self, shape: Shape) {
+        let view_projection = self.camera.view_projection(self.aspect());
+        let lines = shape.0
+            .par_iter()
+            .map(|line| Camera::clip_line(&view_projection, *line));
+        self.draw_lines(lines, shape.1, None);
+    }
+
+    /// Rasterises lines given in clip coordinates, skipping the `None`s, with each thread of
+    /// the pool drawing its share into its own tile before the tiles are merged onto the screen
+    fn draw_lines(
+        &mut self,
+        lines: impl IndexedParallelIterator<Item = Option<Line4d>>,
+        char: char,
+        colour: Option<Colour>,
+    ) {
+        let tile = lines
+            .with_min_len(LINES_PER_TASK)
+            .fold(Tile::new, |mut tile, line| {
+                if let Some(line) = line {
+                    self.rasterise(line, &mut tile);
+                }
+                tile
+            })
+            .reduce(Tile::new, Tile::merge);
+        for (index, _) in tile.0.iter().enumerate().filter(|(_, covered)| **covered) {
+            let (x, y) = (index % N, index / N);
+            self.screen[y][x] = char;
+            self.colours[y][x] = colour;
+        }
+    }
+
+    /// Draws a line given in clip coordinates that has already been clipped to the near plane
+    fn rasterise(&self, clipped: Line4d, tile: &mut Tile<N, M>) {
+        let start = self.buffer_coords(Camera::perspective_divide(clipped[0]));
+        let end = self.buffer_coords(Camera::perspective_divide(clipped[1]));
+        let Some((start, end)) = self.viewport().clip_line(start, end) else {
+            return;
+        };
+
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.);
+        (0..=steps as usize).for_each(|step| {
+            let t = step as f128 / steps;
+            tile.set_point((start.0 + dx * t, start.1 + dy * t));
+        });
+    }
+
+    /// Height of an image of the screen with the given width, keeping its proportions
+    fn image_height(&self, width: f128) -> f128 {
+        width / self.aspect()
+    }
+
+    /// Projects the edges of meshes into an image the same shape as the screen, in pixels from
+    /// its top left corner, dropping whatever falls outside
+    fn project_segments(&self, meshes: &[Mesh], width: f128, height: f128) -> Vec<Segment> {
+        let viewport = Rect {
+            min: (0., 0.),
+            max: (width, height),
+        };
+        let image_coords = |(x, y): (f128, f128)| ((x + 1.) * width / 2., (1. - y) * height / 2.);
+
+        let view_projection = self.camera.view_projection(self.aspect());
+        let mut segments = vec![];
+        for mesh in meshes {
+            let clip = Self::clip_vertices(&view_projection, mesh);
+            for [a, b] in &mesh.edges {
+                let Some(line) = Camera::clip_near(Line4d([clip[*a], clip[*b]])) else {
+                    continue;
+                };
+                let start = image_coords(Camera::perspective_divide(line[0]));
+                let end = image_coords(Camera::perspective_divide(line[1]));
+                let Some((start, end)) = viewport.clip_line(start, end) else {
+                    continue;
+                };
+                // Normalized device depth runs from -1 at the near plane to 1 at the far one
+                let depth = line.iter().map(|point| point[2] / point[3]).sum::<f128>() / 2.;
+                segments.push(Segment {
+                    start,
+                    end,
+                    depth: ((depth + 1.) / 2.).clamp(0., 1.),
+                    colour: mesh.colour,
+                });
+            }
+        }
+        segments
+    }
+
+    /// Width of the screen divided by its height, accounting for characters being taller than
+    /// they are wide
+    fn aspect(&self) -> f128 {
+        N as f128 / (M as f128 * self.char_ratio)
+    }
+
+    /// The visible area of the buffer, in buffer coordinates
+    fn viewport(&self) -> Rect {
+        Rect {
+            min: (0., 0.),
+            max: (N as f128 - 1., M as f128 - 1.),
+        }
+    }
+
+    /// Turns normalized device coordinates into (column, row) coordinates of the buffer
+    fn buffer_coords(&self, (ndc_x, ndc_y): (f128, f128)) -> (f128, f128) {
+        ((ndc_x + 1.) * N as f128 / 2., (1. - ndc_y) * M as f128 / 2.)
+    }
+
+    /// Writes text over the frame from a row and column, cutting off whatever runs past the
+    /// edge of the screen
+    pub fn draw_text(&mut self, row: usize, column: usize, text: &str) {
+        let Some(line) = self.screen.get_mut(row) else {
+            return;
+        };
+        for (cell, char) in line.iter_mut().skip(column).zip(printable(text).chars()) {
+            *cell = char;
+        }
+        for colour in self.colours[row].iter_mut().skip(column).take(text.chars().count()) {
+            *colour = None;
+        }
+    }
+
+    /// The characters on screen, row by row
+    pub fn grid(&self) -> &[[char; N]; M] {
+        &self.screen
+    }
+
+    /// The colour of each character on screen, or `None` where it uses the terminal's own
+    pub fn colours(&self) -> &[[Option<Colour>; N]; M] {
+        &self.colours
+    }
+
+    /// Clears the screen and draws a scene on it, returning the frame as plain text. Nothing
+    /// is written to the terminal
+    pub fn render_to_string(&mut self, scene: &Scene, scale: f128) -> String {
+        self.clear_screen();
+        self.render_scene(scene, scale);
+        self.to_string()
+    }
+
+    pub fn print_screen(&self) {
+        if self.colours.iter().flatten().all(Option::is_none) {
+            println!("{}", MyScreenBuffer(self.screen));
+        } else {
+            for row in 0..M {
+                println!("{}", self.row_text(row));
             }
         }
-        //self.screen[buff_coord_y][buff_coord_x] = char;
     }
 
-    fn print_screen(&self) {
-        println!("{}", MyScreenBuffer(self.screen));
+    /// Writes the screen with colour escape codes, ending each row with `\r\n` so it also
+    /// works in raw mode
+    pub fn print_screen_raw(&self, stdout: &mut impl Write) {
+        for row in 0..M {
+            write!(stdout, "{}\r\n", self.row_text(row)).unwrap();
+        }
     }
 
-    fn print_screen_raw(&self, stdout: &mut Stdout) {
-        for i in self.screen {
-            write!(stdout, "{}\r\n", i.into_iter().collect::<String>()).unwrap();
+    /// A row of the buffer, with escape codes wherever the colour changes
+    fn row_text(&self, row: usize) -> String {
+        let mut text = String::new();
+        let mut current = None;
+        for (char, colour) in self.screen[row].iter().zip(self.colours[row]) {
+            if colour != current {
+                match colour {
+                    Some(Colour([r, g, b])) => text += &Fg(Rgb(r, g, b)).to_string(),
+                    None => text += &Fg(Reset).to_string(),
+                }
+                current = colour;
+            }
+            text.push(*char);
+        }
+        if current.is_some() {
+            text += &Fg(Reset).to_string();
         }
+        text
     }
 
-    fn clear_screen(&mut self) {
+    pub fn clear_screen(&mut self) {
         self.screen = [[' '; N]; M];
+        self.colours = [[None; N]; M];
     }
 }
 
+/// The text with control characters, which would move the cursor or restyle the terminal,
+/// shown as `?`
+fn printable(text: &str) -> String {
+    text.chars()
+        .map(|char| if char.is_control() { '?' } else { char })
+        .collect()
+}
+
+/// The name a script is given for a key, if it is one scripts are told about
+fn key_name(key: Key) -> Option<String> {
+    Some(match key {
+        Key::Char('\n') => "enter".to_string(),
+        Key::Char('\t') => "tab".to_string(),
+        Key::Char(char) => char.to_string(),
+        Key::Left => "left".to_string(),
+        Key::Right => "right".to_string(),
+        Key::Up => "up".to_string(),
+        Key::Down => "down".to_string(),
+        Key::Backspace => "backspace".to_string(),
+        Key::Esc => "escape".to_string(),
+        _ => return None,
+    })
+}
+
 impl<const N: usize, const M: usize> Default for Screen<N, M> {
     fn default() -> Self {
         Screen {
             screen: [[' '; N]; M],
+            colours: [[None; N]; M],
+            camera: Camera::default(),
+            char_ratio: Config::default().char_ratio,
         }
     }
 }
 
+impl<const N: usize, const M: usize> Display for Screen<N, M> {
+    /// The characters on screen without colours, each row ending in a newline
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", MyScreenBuffer(self.screen))
+    }
+}
+
 impl<const N: usize, const M: usize> Display for MyScreenBuffer<N, M> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut display: String = String::new();
@@ -294,9 +750,21 @@ impl<const N: usize, const M: usize> Display for MyScreenBuffer<N, M> {
 
 #[cfg(test)]
 pub mod test {
-    use crate::{line::Line, point::Point, shape::Shape};
+    use crate::{
+        colour::Colour,
+        line::Line,
+        mesh::{fourd::Mesh as Mesh4d, Mesh},
+        point::Point,
+        scene::{Object, Scene},
+        shape::{fourd::Shape as Shape4d, Shape},
+        timeline::{Easing, Track},
+    };
+
+    use std::time::Duration;
 
-    use super::Screen;
+    use rayon::ThreadPoolBuilder;
+
+    use super::{clip::Rect, raster::{self, Image}, svg::SvgOptions, Screen, FIT_MARGIN};
 
     #[test]
     fn print_screen() {
@@ -312,6 +780,129 @@ pub mod test {
         scr.clear_screen();
     }
 
+    #[test]
+    fn headless() {
+        let mut scr: Screen<78, 42> = Screen::default();
+        let cube = Object::new(Shape::cube()).with_glyph('#').with_colour(Colour([0, 255, 0]));
+        let scene = Scene::default().with(cube);
+        let text = scr.render_to_string(&scene, 1.);
+
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 42);
+        assert!(lines.iter().all(|line| line.chars().count() == 78));
+        assert!(text.contains('#'));
+        // The cube sits in the middle, with the colour only where it is drawn
+        assert_eq!(scr.grid()[21][39], ' ');
+        assert!(scr.grid().iter().flatten().zip(scr.colours().iter().flatten()).all(
+            |(char, colour)| (*char == '#') == (*colour == Some(Colour([0, 255, 0])))
+        ));
+
+        let mut ansi = vec![];
+        scr.print_screen_raw(&mut ansi);
+        assert!(String::from_utf8(ansi).unwrap().contains("\x1b[38;2;0;255;0m#"));
+
+        scr.clear_screen();
+        assert!(scr.to_string().chars().all(|char| char == ' ' || char == '\n'));
+    }
+
+    #[test]
+    fn svg() {
+        let scr: Screen<78, 42> = Screen::default();
+        let cube = Mesh::from(Shape::cube()).rotate_z_theta(0.4).rotate_x_theta(0.3);
+        let cube = cube.clone() * scr.fit_scale(std::slice::from_ref(&cube));
+        let svg = scr.render_svg(std::slice::from_ref(&cube), &SvgOptions::default());
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<line").count(), 12);
+        // The far edges are drawn first, thinner than the near ones drawn last
+        let widths = svg
+            .lines()
+            .filter_map(|line| line.split("stroke-width=\"").nth(1))
+            .map(|rest| rest.split('"').next().unwrap().parse::<f64>().unwrap())
+            .collect::<Vec<_>>();
+        assert!(widths.first() < widths.last());
+
+        let flat = SvgOptions { depth_cue: false, ..SvgOptions::default() };
+        let svg = scr.render_svg(&[cube * 10.], &flat);
+        assert!(svg.matches("stroke-width=\"2.00\"").count() == svg.matches("<line").count());
+        // Everything is clipped to the image, which has the proportions of the screen
+        let coords = svg
+            .split(['"'])
+            .collect::<Vec<_>>()
+            .windows(2)
+            .filter(|pair| pair[0].ends_with(" x1=") || pair[0].ends_with(" x2="))
+            .map(|pair| pair[1].parse::<f64>().unwrap())
+            .collect::<Vec<_>>();
+        assert!(!coords.is_empty() && coords.iter().all(|x| (0. ..=800.).contains(x)));
+    }
+
+    #[test]
+    fn raster() {
+        let mut image = Image::new(8, 4, Colour([255; 3]));
+        // A line along the middle of a row fills it, and one between rows shares itself out
+        image.draw_line((0.5, 1.5), (7.5, 1.5), Colour([0; 3]));
+        assert!((1..7).all(|x| image.pixel(x, 1) == Colour([0; 3])));
+        assert!((1..7).all(|x| image.pixel(x, 0) == Colour([255; 3])));
+        image.draw_line((0.5, 3.), (7.5, 3.), Colour([0; 3]));
+        assert!((1..7).all(|x| image.pixel(x, 2) == Colour([128; 3]) && image.pixel(x, 3) == Colour([128; 3])));
+
+        let scr: Screen<78, 42> = Screen::default();
+        let cube = Mesh::from(Shape::cube());
+        let cube = cube.clone() * scr.fit_scale(std::slice::from_ref(&cube));
+        let image = scr.render_image(&[cube], 160);
+        assert_eq!((image.width, image.height), (160, 144));
+        assert!(image.pixels.iter().filter(|pixel| pixel.0[0] < 128).count() > 4 * 144);
+        let mut ppm = vec![];
+        image.write_ppm(&mut ppm).unwrap();
+        assert!(ppm.starts_with(b"P6\n160 144\n255\n"));
+        assert_eq!(ppm.len(), 15 + 160 * 144 * 3);
+
+        let mut png = vec![];
+        image.write_png(&mut png).unwrap();
+        let mut reader = png::Decoder::new(std::io::Cursor::new(png)).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(pixels, ppm[15..]);
+
+        let mut gif = vec![];
+        raster::write_gif(&[image.clone(), image.clone()], Duration::from_millis(50), &mut gif).unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(&gif[..]).unwrap();
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!((frame.width, frame.height, frame.delay), (160, 144, 5));
+            frames += 1;
+        }
+        assert_eq!(frames, 2);
+
+        let small = Image::new(80, 72, Colour([255; 3]));
+        assert!(raster::write_gif(&[image, small], Duration::from_millis(50), vec![]).is_err());
+        let wide = Image::new(70000, 1, Colour([255; 3]));
+        assert!(raster::write_gif(&[wide], Duration::from_millis(50), vec![]).is_err());
+    }
+
+    #[test]
+    fn draw_text() {
+        let mut scr: Screen<8, 2> = Screen::default();
+        scr.draw_text(1, 2, "a\nb\x1b[");
+        assert_eq!(scr.grid()[1].iter().collect::<String>(), "  a?b?[ ");
+        scr.draw_text(2, 0, "off screen");
+    }
+
+    #[test]
+    fn text_frames() {
+        let scene = Scene::default().with(Object {
+            spin: [0., 0.1, 0., 0., 0., 0.],
+            ..Object::new(Shape::cube()).with_glyph('#')
+        });
+        let mut scr: Screen<78, 42> = Screen::default();
+        let scale = scr.scene_scale(&scene);
+        let first = scr.render_to_string(&scene, scale);
+        let frames = scr.text_frames(scene, 3).collect::<Vec<_>>();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0], first);
+        assert_ne!(frames[1], frames[0]);
+        assert!(frames.iter().all(|frame| frame.lines().count() == 42 && !frame.contains('\x1b')));
+    }
+
     #[test]
     fn line_in_between() {
         let line: Line = Line([Point([1., 1., 1.]), Point([1., 1., -1.])]);
@@ -319,4 +910,60 @@ pub mod test {
             .iter()
             .for_each(|pt| println!("{pt}"));
     }
+
+    #[test]
+    fn clip_to_viewport() {
+        let rect = Rect { min: (0., 0.), max: (77., 41.) };
+        let (start, end) = rect.clip_line((-10., 20.), (100., 20.)).unwrap();
+        assert_eq!(start, (0., 20.));
+        assert_eq!(end, (77., 20.));
+        assert!(rect.clip_line((-10., -10.), (-1., 50.)).is_none());
+        assert!(rect.clip_line((-10., 5.), (5., -10.)).is_none());
+
+        // A line much longer than the screen still ends exactly at the border
+        let mut scr: Screen<78, 42> = Screen::default();
+        let line = Line([Point([-100., 0., 0.]), Point([100., 0., 0.])]);
+        scr.render_shape(Shape(vec![line], '*'));
+        assert_eq!(scr.screen[21][0], '*');
+        assert_eq!(scr.screen[21][77], '*');
+    }
+
+    #[test]
+    fn parallel() {
+        let mesh = Mesh4d::from(Shape4d::sixhundredcell())
+            .rotate_xw_theta(0.3)
+            .to_mesh_3d()
+            * 2.;
+        let frame = |threads| {
+            let pool = ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            let mut scr: Screen<78, 42> = Screen::default();
+            pool.install(|| scr.render_mesh(&mesh));
+            scr.to_string()
+        };
+        assert_eq!(frame(1), frame(4));
+    }
+
+    #[test]
+    fn fit_to_screen() {
+        let scr: Screen<78, 42> = Screen::default();
+        let cube = Mesh::from(Shape::cube());
+        let fitted = cube.clone() * scr.fit_scale(std::slice::from_ref(&cube));
+        let bounds = scr.projected_bounds(&fitted).unwrap();
+        let extent = [bounds.min.0, bounds.min.1, bounds.max.0, bounds.max.1]
+            .into_iter()
+            .map(f128::abs)
+            .fold(0., f128::max);
+        assert!((extent - FIT_MARGIN).abs() < 1e-6);
+
+        // A camera that moves in is fitted where it ends up, not where it starts
+        let scene = Scene {
+            distance: Track::default()
+                .key(0., scr.camera.distance(), Easing::Linear)
+                .key(6., scr.camera.distance() / 2., Easing::Linear),
+            ..Scene::default().with(Object::new(Shape::cube()))
+        };
+        let mut near = scr;
+        near.camera.dolly(0.5);
+        assert!((scr.scene_scale(&scene) - near.fit_scale(&[scene.combined()])).abs() < 1e-6);
+    }
 }
@@ -0,0 +1,71 @@
+/// Outcode bits for Cohen-Sutherland clipping
+const INSIDE: u8 = 0b0000;
+const LEFT: u8 = 0b0001;
+const RIGHT: u8 = 0b0010;
+const TOP: u8 = 0b0100;
+const BOTTOM: u8 = 0b1000;
+
+/// An axis aligned rectangle in screen space, inclusive of its edges
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub min: (f128, f128),
+    pub max: (f128, f128),
+}
+
+impl Rect {
+    fn outcode(&self, (x, y): (f128, f128)) -> u8 {
+        let mut code = INSIDE;
+        if x < self.min.0 {
+            code |= LEFT;
+        } else if x > self.max.0 {
+            code |= RIGHT;
+        }
+        if y < self.min.1 {
+            code |= TOP;
+        } else if y > self.max.1 {
+            code |= BOTTOM;
+        }
+        code
+    }
+
+    /// Clips the segment from `start` to `end` to the rectangle using Cohen-Sutherland.
+    /// Returns `None` if no part of the segment is inside
+    pub fn clip_line(
+        &self,
+        mut start: (f128, f128),
+        mut end: (f128, f128),
+    ) -> Option<((f128, f128), (f128, f128))> {
+        if !(start.0.is_finite() && start.1.is_finite() && end.0.is_finite() && end.1.is_finite()) {
+            return None;
+        }
+        let mut start_code = self.outcode(start);
+        let mut end_code = self.outcode(end);
+        loop {
+            if start_code | end_code == INSIDE {
+                return Some((start, end));
+            }
+            if start_code & end_code != INSIDE {
+                return None;
+            }
+            // At least one endpoint is outside, move it onto the edge it is beyond
+            let outside = if start_code != INSIDE { start_code } else { end_code };
+            let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+            let point = if outside & TOP != 0 {
+                (start.0 + dx * (self.min.1 - start.1) / dy, self.min.1)
+            } else if outside & BOTTOM != 0 {
+                (start.0 + dx * (self.max.1 - start.1) / dy, self.max.1)
+            } else if outside & LEFT != 0 {
+                (self.min.0, start.1 + dy * (self.min.0 - start.0) / dx)
+            } else {
+                (self.max.0, start.1 + dy * (self.max.0 - start.0) / dx)
+            };
+            if outside == start_code {
+                start = point;
+                start_code = self.outcode(start);
+            } else {
+                end = point;
+                end_code = self.outcode(end);
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use super::Screen;
+use crate::{colour::Colour, mesh::Mesh};
+
+/// A picture made of pixels, in rows from the top left
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Colour>,
+}
+
+const WHITE: Colour = Colour([255; 3]);
+const BLACK: Colour = Colour([0; 3]);
+
+impl Image {
+    pub fn new(width: usize, height: usize, background: Colour) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Colour {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Mixes a colour into a pixel, covering the given fraction of it
+    fn blend(&mut self, x: i64, y: i64, colour: Colour, coverage: f64) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let pixel = &mut self.pixels[y as usize * self.width + x as usize];
+        for (old, new) in pixel.0.iter_mut().zip(colour.0) {
+            *old = (*old as f64 * (1. - coverage) + new as f64 * coverage).round() as u8;
+        }
+    }
+
+    /// Draws an anti-aliased line between two points given in pixels, using Xiaolin Wu's
+    /// algorithm
+    pub fn draw_line(&mut self, start: (f64, f64), end: (f64, f64), colour: Colour) {
+        // The algorithm puts pixel centres on whole numbers
+        let (mut x0, mut y0) = (start.0 - 0.5, start.1 - 0.5);
+        let (mut x1, mut y1) = (end.0 - 0.5, end.1 - 0.5);
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep {
+            (x0, y0, x1, y1) = (y0, x0, y1, x1);
+        }
+        if x0 > x1 {
+            (x0, y0, x1, y1) = (x1, y1, x0, y0);
+        }
+        let gradient = if x1 == x0 { 0. } else { (y1 - y0) / (x1 - x0) };
+        let mut plot = |x: f64, y: f64, coverage: f64| {
+            let (x, y) = if steep { (y, x) } else { (x, y) };
+            self.blend(x as i64, y as i64, colour, coverage);
+        };
+        let fract = |x: f64| x - x.floor();
+
+        // The ends cover only the part of their pixel the line reaches into
+        let mut ends =
+            [(x0, y0, 1. - fract(x0 + 0.5)), (x1, y1, fract(x1 + 0.5))].map(|(x, y, gap)| {
+                let x_end = x.round();
+                let y_end = y + gradient * (x_end - x);
+                plot(x_end, y_end.floor(), (1. - fract(y_end)) * gap);
+                plot(x_end, y_end.floor() + 1., fract(y_end) * gap);
+                (x_end, y_end)
+            });
+        if ends[0].0 == ends[1].0 {
+            return;
+        }
+        ends[0].1 += gradient;
+        let mut y = ends[0].1;
+        let mut x = ends[0].0 + 1.;
+        while x < ends[1].0 {
+            plot(x, y.floor(), 1. - fract(y));
+            plot(x, y.floor() + 1., fract(y));
+            y += gradient;
+            x += 1.;
+        }
+    }
+
+    /// The pixels as bytes, red, green and blue for each
+    fn rgb(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|pixel| pixel.0).collect()
+    }
+
+    /// Writes the image as a binary PPM, the simplest format most image tools can read
+    pub fn write_ppm(&self, mut out: impl Write) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        out.write_all(&self.rgb())
+    }
+
+    pub fn write_png(&self, out: impl Write) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(out, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.rgb())?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Writes images as the frames of a GIF that loops forever. Every frame must be the same size,
+/// and no more than 65535 pixels across or down
+pub fn write_gif(frames: &[Image], delay: Duration, out: impl Write) -> io::Result<()> {
+    let Some(first) = frames.first() else {
+        return Err(io::Error::other("a GIF needs at least one frame"));
+    };
+    let (Ok(width), Ok(height)) = (u16::try_from(first.width), u16::try_from(first.height)) else {
+        return Err(io::Error::other(format!(
+            "a {}x{} image is too large for a GIF, which can be at most 65535 pixels a side",
+            first.width, first.height
+        )));
+    };
+    if let Some(image) = frames
+        .iter()
+        .find(|image| (image.width, image.height) != (first.width, first.height))
+    {
+        return Err(io::Error::other(format!(
+            "every frame of a GIF must be the same size, but a {}x{} frame follows a {}x{} one",
+            image.width, image.height, first.width, first.height
+        )));
+    }
+    let mut encoder = gif::Encoder::new(out, width, height, &[]).map_err(io::Error::other)?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(io::Error::other)?;
+    // GIF delays are in hundredths of a second
+    let delay = (delay.as_millis() / 10) as u16;
+    for image in frames {
+        let mut frame = gif::Frame::from_rgb_speed(width, height, &image.rgb(), 10);
+        frame.delay = delay;
+        encoder.write_frame(&frame).map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+impl<const N: usize, const M: usize> Screen<N, M> {
+    /// Draws meshes into an image of the frame the screen would show, with smooth lines in
+    /// each mesh's colour, or black, on white. The image has the same proportions as the screen
+    pub fn render_image(&self, meshes: &[Mesh], width: usize) -> Image {
+        let height = self.image_height(width as f128).round() as usize;
+        let mut image = Image::new(width, height, WHITE);
+        let mut segments = self.project_segments(meshes, width as f128, height as f128);
+        segments.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+        for segment in segments {
+            let point = |(x, y): (f128, f128)| (x as f64, y as f64);
+            image.draw_line(
+                point(segment.start),
+                point(segment.end),
+                segment.colour.unwrap_or(BLACK),
+            );
+        }
+        image
+    }
+}
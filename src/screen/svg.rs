@@ -0,0 +1,82 @@
+use std::fmt::Write;
+
+use super::Screen;
+use crate::{colour::Colour, mesh::Mesh};
+
+/// How an SVG frame is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SvgOptions {
+    /// Width of the image. The height follows from the shape of the screen
+    pub width: f128,
+    pub stroke_width: f128,
+    /// Draw nearer lines thicker and more opaque than further ones
+    pub depth_cue: bool,
+    /// Fill the background in white rather than leaving it transparent
+    pub background: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            width: 800.,
+            stroke_width: 2.,
+            depth_cue: true,
+            background: true,
+        }
+    }
+}
+
+impl<const N: usize, const M: usize> Screen<N, M> {
+    /// Draws meshes as an SVG image of the frame the screen would show, with each edge as a
+    /// true line rather than characters. The image has the same proportions as the screen
+    pub fn render_svg(&self, meshes: &[Mesh], options: &SvgOptions) -> String {
+        let width = options.width;
+        let height = self.image_height(width);
+        let mut segments = self.project_segments(meshes, width, height);
+        // Draw from the back so nearer lines are on top
+        segments.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+        let (nearest, furthest) = segments.iter().fold((1., 0.), |(near, far), segment| {
+            (
+                f128::min(near, segment.depth),
+                f128::max(far, segment.depth),
+            )
+        });
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.2}" height="{h:.2}" viewBox="0 0 {w:.2} {h:.2}">"#,
+            w = width as f64,
+            h = height as f64,
+        )
+        .unwrap();
+        if options.background {
+            writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+        }
+        writeln!(svg, r#"<g stroke-linecap="round" fill="none">"#).unwrap();
+        for segment in &segments {
+            // How far back the line is among the lines in the frame, from 0 to 1
+            let depth = if options.depth_cue && furthest > nearest {
+                (segment.depth - nearest) / (furthest - nearest)
+            } else {
+                0.
+            };
+            let stroke_width = options.stroke_width * (1. - 0.6 * depth);
+            let opacity = 1. - 0.7 * depth;
+            writeln!(
+                svg,
+                r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}" stroke-width="{:.2}" stroke-opacity="{:.2}"/>"#,
+                segment.start.0 as f64,
+                segment.start.1 as f64,
+                segment.end.0 as f64,
+                segment.end.1 as f64,
+                segment.colour.unwrap_or(Colour([0; 3])),
+                stroke_width as f64,
+                opacity as f64,
+            )
+            .unwrap();
+        }
+        writeln!(svg, "</g>\n</svg>").unwrap();
+        svg
+    }
+}
@@ -0,0 +1,442 @@
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+
+use crate::{
+    camera::Camera,
+    colour::Colour,
+    mesh::is_glyph,
+    point::Point,
+    scene::{
+        file::{angles, check_distance, frames_per_second, ObjectSection, Source},
+        Object, Scene,
+    },
+    timeline::{Easing, Orientation, Track},
+};
+
+type Result<T> = std::result::Result<T, Box<EvalAltResult>>;
+
+/// Most operations the top level or one call of a script may take, so a script stuck in a loop
+/// fails with an error instead of freezing the screen it draws on
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+/// Longest string, and most items in an array or map, a script may make, so one that keeps
+/// doubling a value fails with an error instead of running out of memory
+const MAX_SIZE: usize = 100_000;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The script would not compile, or failed while running, with rhai's message saying where
+    Script(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{err}"),
+            Error::Script(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<Box<EvalAltResult>> for Error {
+    fn from(err: Box<EvalAltResult>) -> Self {
+        Error::Script(err.to_string())
+    }
+}
+
+/// Text a script put over the frame, at a row and column of the screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Text {
+    pub row: usize,
+    pub column: usize,
+    pub text: String,
+}
+
+/// Everything the functions given to a script can change
+#[derive(Debug, Default)]
+struct State {
+    scene: Scene,
+    text: Vec<Text>,
+    /// The last thing the script printed
+    status: String,
+}
+
+/// A Rhai script that builds a scene and changes it as it runs. The script's top level runs
+/// once, to set the scene up. After that, a `frame(n)` function, if it has one, runs before
+/// each frame is drawn, and a `key(name)` function runs for each key pressed. Both can keep
+/// values from one call to the next in `this`, which starts as an empty map
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    state: Rc<RefCell<State>>,
+    this: Dynamic,
+    frame: i64,
+}
+
+impl Script {
+    /// Compiles a script and runs its top level. Shape files are found relative to `dir`
+    pub fn new(source: &str, dir: &Path) -> std::result::Result<Script, Error> {
+        let state = Rc::new(RefCell::new(State::default()));
+        let engine = engine(&state, dir.to_path_buf());
+        let ast = engine
+            .compile(source)
+            .map_err(|err| Error::Script(err.to_string()))?;
+        engine.run_ast(&ast)?;
+        Ok(Script {
+            engine,
+            ast,
+            state,
+            this: Dynamic::from_map(Map::new()),
+            frame: 0,
+        })
+    }
+
+    pub fn load(path: &Path) -> std::result::Result<Script, Error> {
+        let source = fs::read_to_string(path)?;
+        Script::new(&source, path.parent().unwrap_or(Path::new(".")))
+    }
+
+    /// Calls one of the script's functions, if it has one taking that many arguments
+    fn call(&mut self, name: &str, args: impl rhai::FuncArgs) -> std::result::Result<(), Error> {
+        let mut values = vec![];
+        args.parse(&mut values);
+        let defined = self
+            .ast
+            .iter_functions()
+            .any(|function| function.name == name && function.params.len() == values.len());
+        if !defined {
+            return Ok(());
+        }
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.this);
+        self.engine
+            .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, name, values)
+            .map(|_| ())
+            .map_err(Error::from)
+    }
+
+    /// Moves the scene on to the next frame and lets the script change it. The first call
+    /// gives the scene as it was set up
+    pub fn frame(&mut self) -> std::result::Result<(), Error> {
+        {
+            let mut state = self.state.borrow_mut();
+            if self.frame > 0 {
+                state.scene.step();
+            }
+            state.text.clear();
+        }
+        let frame = self.frame;
+        self.frame += 1;
+        self.call("frame", (frame,))
+    }
+
+    /// Tells the script a key was pressed: a letter or other character, or `left`, `right`,
+    /// `up`, `down`, `enter`, `backspace`, `tab` or `escape`
+    pub fn key(&mut self, name: &str) -> std::result::Result<(), Error> {
+        self.call("key", (name.to_string(),))
+    }
+
+    pub fn scene(&self) -> Scene {
+        self.state.borrow().scene.clone()
+    }
+
+    /// The text to draw over the current frame
+    pub fn text(&self) -> Vec<Text> {
+        self.state.borrow().text.clone()
+    }
+
+    /// The last thing the script printed
+    pub fn status(&self) -> String {
+        self.state.borrow().status.clone()
+    }
+}
+
+fn number(value: &Dynamic) -> Result<f64> {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|int| int as f64))
+        .map_err(|_| format!("expected a number, found {}", value.type_name()).into())
+}
+
+fn numbers<const N: usize>(values: &Array) -> Result<[f64; N]> {
+    if values.len() != N {
+        return Err(format!("expected {N} numbers, found {}", values.len()).into());
+    }
+    let mut numbers = [0.; N];
+    for (slot, value) in numbers.iter_mut().zip(values) {
+        *slot = number(value)?;
+    }
+    Ok(numbers)
+}
+
+/// An engine with cubers' functions for changing the scene in `state`
+fn engine(state: &Rc<RefCell<State>>, dir: PathBuf) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_string_size(MAX_SIZE);
+    engine.set_max_array_size(MAX_SIZE);
+    engine.set_max_map_size(MAX_SIZE);
+    // Rhai prints both to stdout by default, which would scribble over the screen
+    let printed = state.clone();
+    engine.on_print(move |text| printed.borrow_mut().status = text.to_string());
+    let debugged = state.clone();
+    engine.on_debug(move |text, _, _| debugged.borrow_mut().status = text.to_string());
+
+    // Runs `change` on an object of the scene, failing if there is no such object
+    let with_object = |state: &Rc<RefCell<State>>| {
+        let state = state.clone();
+        move |id: i64, change: &dyn Fn(&mut Object) -> Result<()>| -> Result<()> {
+            let mut state = state.borrow_mut();
+            let object = usize::try_from(id)
+                .ok()
+                .and_then(|id| state.scene.objects.get_mut(id))
+                .ok_or_else(|| format!("there is no object {id}"))?;
+            change(object)
+        }
+    };
+
+    let added = state.clone();
+    engine.register_fn("add", move |spec: Dynamic| -> Result<i64> {
+        // Read the same way as a scene file, which takes whole numbers wherever it takes numbers
+        let value = toml::Value::try_from(&spec).map_err(|err| err.to_string())?;
+        let is_object = value
+            .as_table()
+            .is_some_and(|table| table.contains_key("shape"));
+        let section = if is_object {
+            value.try_into::<ObjectSection>()
+        } else {
+            value.try_into::<Source>().map(ObjectSection::new)
+        }
+        .map_err(|err| err.to_string().trim_end().to_string())?;
+        let object = section.object(&dir).map_err(|err| err.to_string())?;
+        let mut state = added.borrow_mut();
+        state.scene.objects.push(object);
+        Ok(state.scene.objects.len() as i64 - 1)
+    });
+
+    let object = with_object(state);
+    engine.register_fn("rotate", move |id: i64, rotation: Array| -> Result<()> {
+        let orientation = Orientation::from_angles(angles(numbers(&rotation)?));
+        object(id, &|object| {
+            object.timeline.orientation = Track::default().key(0., orientation, Easing::Linear);
+            Ok(())
+        })
+    });
+    let object = with_object(state);
+    engine.register_fn("spin", move |id: i64, spin: Array| -> Result<()> {
+        let spin = angles(numbers(&spin)?).map(|theta| theta / frames_per_second());
+        object(id, &|object| {
+            object.spin = spin;
+            Ok(())
+        })
+    });
+    let object = with_object(state);
+    engine.register_fn("move_to", move |id: i64, position: Array| -> Result<()> {
+        let position = Point(numbers(&position)?.map(|x| x as f128));
+        object(id, &|object| {
+            object.position = position;
+            Ok(())
+        })
+    });
+    let object = with_object(state);
+    engine.register_fn("resize", move |id: i64, scale: Dynamic| -> Result<()> {
+        let scale = number(&scale)? as f128;
+        object(id, &|object| {
+            object.scale = scale;
+            Ok(())
+        })
+    });
+    let object = with_object(state);
+    engine.register_fn("colour", move |id: i64, colour: &str| -> Result<()> {
+        let colour = colour.parse::<Colour>().map_err(|err| err.to_string())?;
+        object(id, &|object| {
+            object.timeline.colour = Track::default();
+            *object = object.clone().with_colour(colour);
+            Ok(())
+        })
+    });
+    let object = with_object(state);
+    engine.register_fn("glyph", move |id: i64, glyph: char| -> Result<()> {
+        if !is_glyph(glyph) {
+            return Err(format!("{glyph:?} cannot be a glyph").into());
+        }
+        object(id, &|object| {
+            *object = object.clone().with_glyph(glyph);
+            Ok(())
+        })
+    });
+
+    let camera = state.clone();
+    engine.register_fn("distance", move |distance: Dynamic| -> Result<()> {
+        let distance = number(&distance)?;
+        check_distance(distance).map_err(|err| err.to_string())?;
+        let distance = distance as f128;
+        let mut state = camera.borrow_mut();
+        let camera = state.scene.camera.get_or_insert_with(Camera::default);
+        camera.dolly(distance / camera.distance());
+        Ok(())
+    });
+    let camera = state.clone();
+    engine.register_fn("orbit", move |yaw: Dynamic, pitch: Dynamic| -> Result<()> {
+        let [yaw, pitch] =
+            [number(&yaw)?, number(&pitch)?].map(|angle| (angle as f128).to_radians());
+        let mut state = camera.borrow_mut();
+        state
+            .scene
+            .camera
+            .get_or_insert_with(Camera::default)
+            .orbit(yaw, pitch);
+        Ok(())
+    });
+
+    let text = state.clone();
+    engine.register_fn(
+        "text",
+        move |row: i64, column: i64, string: &str| -> Result<()> {
+            let (Ok(row), Ok(column)) = (usize::try_from(row), usize::try_from(column)) else {
+                return Err("text needs a row and column of 0 or more".into());
+            };
+            text.borrow_mut().text.push(Text {
+                row,
+                column,
+                text: string.to_string(),
+            });
+            Ok(())
+        },
+    );
+    engine
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::path::Path;
+
+    use super::{Error, Script, Text};
+    use crate::{colour::Colour, mesh::Model};
+
+    #[test]
+    fn scripts() {
+        let mut script =
+            Script::new(include_str!("../scenes/controls.rhai"), Path::new(".")).unwrap();
+        assert_eq!(script.scene().objects.len(), 2);
+        assert!(script.status().contains("q quits"));
+
+        script.frame().unwrap();
+        let first = script.scene().meshes();
+        assert_eq!(
+            script.text()[0],
+            Text {
+                row: 0,
+                column: 0,
+                text: "frame 0".to_string(),
+            }
+        );
+        assert_eq!(first[1].colour, Some(Colour([255, 96, 64])));
+
+        // A key turns the hypercube on the next frame, and the angle is kept in `this`
+        script.key("n").unwrap();
+        script.key("n").unwrap();
+        script.frame().unwrap();
+        assert_eq!(script.text()[0].text, "frame 1");
+        assert_eq!(script.text()[1].text, "xy 0, xz 0, yz 0, xw 10, yw 0, zw 0");
+        let second = script.scene().meshes();
+        assert!(first[0]
+            .vertices
+            .iter()
+            .zip(&second[0].vertices)
+            .any(|(a, b)| (*a - *b).l2_norm() > 1e-3));
+        assert!(first[1]
+            .vertices
+            .iter()
+            .zip(&second[1].vertices)
+            .all(|(a, b)| (*a - *b).l2_norm() < 1e-9));
+    }
+
+    #[test]
+    fn api() {
+        let mut script = Script::new(
+            r##"
+            let torus = add(#{ torus: [8, 4] });
+            let prism = add(#{ shape: #{ duoprism: [3, 4] }, glyph: '+' });
+            spin(prism, [0, 0, 0, 0, 0, 20]);
+            move_to(torus, [1, 2, 3]);
+            resize(torus, 2);
+            colour(torus, "#00ff00");
+            glyph(torus, '@');
+            orbit(90, 0);
+            distance(10);
+            fn frame(n) {
+                print(`frame ${n}`);
+            }
+            "##,
+            Path::new("."),
+        )
+        .unwrap();
+        script.frame().unwrap();
+        assert_eq!(script.status(), "frame 0");
+        let scene = script.scene();
+        let Model::Mesh4d(prism) = &scene.objects[1].model else {
+            panic!("a duoprism is 4d");
+        };
+        assert_eq!(prism.glyph, '+');
+        assert!((scene.objects[1].spin[5] - 1f128.to_radians()).abs() < 1e-9);
+        let torus = &scene.meshes()[0];
+        assert_eq!(
+            (torus.glyph, torus.colour),
+            ('@', Some(Colour([0, 255, 0])))
+        );
+        assert_eq!(scene.objects[0].scale, 2.);
+        assert!((scene.camera.unwrap().distance() - 10.).abs() < 1e-9);
+
+        let error = |source| Script::new(source, Path::new(".")).err().unwrap();
+        assert!(matches!(error("let x = ;"), Error::Script(_)));
+        assert!(matches!(error("add(\"cubee\")"), Error::Script(_)));
+        assert!(matches!(
+            error("rotate(3, [0, 0, 0, 0, 0, 0])"),
+            Error::Script(_)
+        ));
+        assert!(matches!(
+            error("add(\"cube\"); rotate(0, [1, 2])"),
+            Error::Script(_)
+        ));
+        let mut failing = Script::new("fn key(name) { add(name) }", Path::new(".")).unwrap();
+        assert!(failing.key("x").is_err());
+
+        // A script stuck in a loop runs out of operations rather than hanging
+        assert!(matches!(error("loop {}"), Error::Script(_)));
+        let mut stuck = Script::new("fn frame(n) { loop {} }", Path::new(".")).unwrap();
+        assert!(matches!(stuck.frame(), Err(Error::Script(_))));
+        // Nor can it run out of memory
+        assert!(matches!(
+            error("let s = \"x\"; for i in 0..40 { s += s }"),
+            Error::Script(_)
+        ));
+        assert!(matches!(
+            error("let a = [1]; for i in 0..40 { a += a }"),
+            Error::Script(_)
+        ));
+
+        for source in ["distance(0)", "distance(-3)", "add(\"cube\"); glyph(0, ' ')"] {
+            assert!(matches!(error(source), Error::Script(_)), "{source}");
+        }
+        let debugged = Script::new("debug(\"here\")", Path::new(".")).unwrap();
+        assert_eq!(debugged.status(), "\"here\"");
+    }
+}
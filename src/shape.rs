@@ -1,9 +1,21 @@
-use std::ops::{Deref, DerefMut, Mul};
+use std::{
+    collections::HashSet,
+    f128::consts::{PI, TAU},
+    ops::{Deref, DerefMut, Mul},
+};
 
-use crate::{line::Line, point::Point};
+use itertools::Itertools;
+
+use crate::{
+    line::{fourd::Line as Line4d, Line},
+    point::{fourd::Point as Point4d, Point},
+};
 
 pub mod fourd;
 
+/// The golden ratio
+pub const PHI: f128 = 1.618033988749894848204586834365638117720;
+
 #[derive(Debug, Default, Clone)]
 pub struct Shape(pub Vec<Line>, pub char);
 
@@ -67,4 +79,356 @@ impl Shape {
         ], 
         '*')
     }
+
+    /// Distinct endpoints of the shape's lines, in order of first appearance
+    pub fn vertices(&self) -> Vec<Point> {
+        let mut seen = HashSet::new();
+        self.0
+            .iter()
+            .flat_map(|line| line.0)
+            .filter(|point| seen.insert(point.grid_key()))
+            .collect()
+    }
+
+    /// Connects every pair of vertices that are the minimum distance apart. This gives the
+    /// edges of any polyhedron whose edges are all the same length and shorter than any other
+    /// vertex to vertex distance, which includes the Platonic and Archimedean solids
+    pub fn from_vertices(vertices: &[Point]) -> Shape {
+        Shape(nearest_pairs(vertices), '*')
+    }
+
+    /// A regular polygon with unit edges, centred on the origin in the xy plane
+    pub fn polygon(sides: usize) -> Shape {
+        let radius = 0.5 / (PI / sides as f128).sin();
+        let vertices = (0..sides)
+            .map(|i| {
+                let angle = TAU * i as f128 / sides as f128;
+                Point([radius * angle.cos(), radius * angle.sin(), 0.])
+            })
+            .collect_vec();
+        let lines = vertices
+            .iter()
+            .circular_tuple_windows()
+            .map(|(a, b)| Line([*a, *b]));
+        Shape(lines.collect_vec(), '*')
+    }
+
+    /// A single line of the given length, centred on the origin along the x axis
+    pub fn segment(length: f128) -> Shape {
+        Shape(
+            vec![Line([Point([-length / 2., 0., 0.]), Point([length / 2., 0., 0.])])],
+            '*',
+        )
+    }
+
+    /// Samples `f` along the unit interval at `steps` + 1 evenly spaced points, joined in order
+    pub fn curve(f: impl Fn(f128) -> Point, steps: usize) -> Shape {
+        Shape(curve(f, steps), '*')
+    }
+
+    /// Samples `f` over the unit square on a grid of `u_steps` by `v_steps` cells, joining each
+    /// sample to its neighbours along u and v. Where the surface closes up on itself, or a row
+    /// of samples meets at a pole, the lines that land on top of each other are only kept once
+    pub fn surface(f: impl Fn(f128, f128) -> Point, u_steps: usize, v_steps: usize) -> Shape {
+        Shape(surface(f, u_steps, v_steps), '*')
+    }
+
+    /// A ring torus around the z axis, with the tube half the radius of the ring
+    pub fn torus(u_steps: usize, v_steps: usize) -> Shape {
+        Shape::surface(
+            |u, v| {
+                let (u, v) = (u * TAU, v * TAU);
+                let radius = 1. + 0.5 * v.cos();
+                Point([radius * u.cos(), radius * u.sin(), 0.5 * v.sin()])
+            },
+            u_steps,
+            v_steps,
+        )
+    }
+
+    /// The unit sphere, with `u_steps` lines of longitude and `v_steps` bands of latitude
+    pub fn sphere(u_steps: usize, v_steps: usize) -> Shape {
+        Shape::surface(
+            |u, v| {
+                let (u, v) = (u * TAU, v * PI);
+                Point([v.sin() * u.cos(), v.sin() * u.sin(), v.cos()])
+            },
+            u_steps,
+            v_steps,
+        )
+    }
+
+    /// The figure-8 immersion of the Klein bottle, which passes through itself along a circle
+    /// in the xy plane
+    pub fn klein_bottle(u_steps: usize, v_steps: usize) -> Shape {
+        Shape::surface(
+            |u, v| {
+                let (u, v) = (u * TAU, v * TAU);
+                let radius = 1.5 + (u / 2.).cos() * v.sin() - (u / 2.).sin() * (2. * v).sin();
+                let z = (u / 2.).sin() * v.sin() + (u / 2.).cos() * (2. * v).sin();
+                Point([radius * u.cos(), radius * u.sin(), z])
+            },
+            u_steps,
+            v_steps,
+        )
+    }
+
+    /// A Möbius strip of unit radius and width, with `v_steps` bands across its width
+    pub fn mobius_strip(u_steps: usize, v_steps: usize) -> Shape {
+        Shape::surface(
+            |u, v| {
+                let (u, v) = (u * TAU, v - 0.5);
+                let radius = 1. + v * (u / 2.).cos();
+                Point([radius * u.cos(), radius * u.sin(), v * (u / 2.).sin()])
+            },
+            u_steps,
+            v_steps,
+        )
+    }
+
+    pub fn tetrahedron() -> Shape {
+        Shape::from_vertices(&[
+            Point([ 1.,  1.,  1.]),
+            Point([ 1., -1., -1.]),
+            Point([-1.,  1., -1.]),
+            Point([-1., -1.,  1.]),
+        ])
+    }
+
+    pub fn octahedron() -> Shape {
+        Shape::from_vertices(&expand([1., 0., 0.], false))
+    }
+
+    pub fn dodecahedron() -> Shape {
+        let vertices = [
+            expand([1., 1., 1.], false),
+            expand([0., 1. / PHI, PHI], true),
+        ]
+        .concat();
+        Shape::from_vertices(&vertices)
+    }
+
+    pub fn icosahedron() -> Shape {
+        Shape::from_vertices(&expand([0., 1., PHI], true))
+    }
+
+    pub fn truncated_tetrahedron() -> Shape {
+        let vertices = expand::<Point, 3>([3., 1., 1.], false)
+            .into_iter()
+            .filter(|point| point.iter().filter(|x| **x < 0.).count() % 2 == 0)
+            .collect_vec();
+        Shape::from_vertices(&vertices)
+    }
+
+    pub fn cuboctahedron() -> Shape {
+        Shape::from_vertices(&expand([1., 1., 0.], false))
+    }
+
+    pub fn truncated_cube() -> Shape {
+        Shape::from_vertices(&expand([std::f128::consts::SQRT_2 - 1., 1., 1.], false))
+    }
+
+    pub fn truncated_octahedron() -> Shape {
+        Shape::from_vertices(&expand([0., 1., 2.], false))
+    }
+
+    pub fn rhombicuboctahedron() -> Shape {
+        Shape::from_vertices(&expand([1., 1., 1. + std::f128::consts::SQRT_2], false))
+    }
+
+    pub fn truncated_cuboctahedron() -> Shape {
+        let sqrt_2 = std::f128::consts::SQRT_2;
+        Shape::from_vertices(&expand([1., 1. + sqrt_2, 1. + 2. * sqrt_2], false))
+    }
+
+    pub fn icosidodecahedron() -> Shape {
+        let vertices = [
+            expand([0., 0., PHI], false),
+            expand([0.5, PHI / 2., PHI * PHI / 2.], true),
+        ]
+        .concat();
+        Shape::from_vertices(&vertices)
+    }
+
+    /// The shape of a football, or buckminsterfullerene
+    pub fn truncated_icosahedron() -> Shape {
+        let vertices = [
+            expand([0., 1., 3. * PHI], true),
+            expand([1., 2. + PHI, 2. * PHI], true),
+            expand([PHI, 2., PHI.powi(3)], true),
+        ]
+        .concat();
+        Shape::from_vertices(&vertices)
+    }
+}
+
+/// A 3d or 4d point, so that shapes of either are built from their points the same way
+pub trait Vertex<const D: usize>: Copy + Deref<Target = [f128; D]> {
+    /// A line between two of these points
+    type Line;
+
+    fn new(coords: [f128; D]) -> Self;
+
+    fn join(self, end: Self) -> Self::Line;
+
+    fn grid_key(&self) -> [i64; D];
+
+    fn distance(&self, other: &Self) -> f128;
+}
+
+impl Vertex<3> for Point {
+    type Line = Line;
+
+    fn new(coords: [f128; 3]) -> Point {
+        Point(coords)
+    }
+
+    fn join(self, end: Point) -> Line {
+        Line([self, end])
+    }
+
+    fn grid_key(&self) -> [i64; 3] {
+        Point::grid_key(self)
+    }
+
+    fn distance(&self, other: &Point) -> f128 {
+        (*self - *other).l2_norm()
+    }
+}
+
+impl Vertex<4> for Point4d {
+    type Line = Line4d;
+
+    fn new(coords: [f128; 4]) -> Point4d {
+        Point4d(coords)
+    }
+
+    fn join(self, end: Point4d) -> Line4d {
+        Line4d([self, end])
+    }
+
+    fn grid_key(&self) -> [i64; 4] {
+        Point4d::grid_key(self)
+    }
+
+    fn distance(&self, other: &Point4d) -> f128 {
+        (*self - *other).l2_norm()
+    }
+}
+
+/// Lines between every pair of vertices that are the minimum distance apart
+fn nearest_pairs<P: Vertex<D>, const D: usize>(vertices: &[P]) -> Vec<P::Line> {
+    let shortest = vertices
+        .iter()
+        .tuple_combinations()
+        .map(|(a, b)| a.distance(b))
+        .fold(f128::INFINITY, f128::min);
+    vertices
+        .iter()
+        .tuple_combinations()
+        .filter(|(a, b)| a.distance(b) < shortest * (1. + 1e-9))
+        .map(|(a, b)| a.join(*b))
+        .collect()
+}
+
+/// Lines through `f` sampled along the unit interval at `steps` + 1 evenly spaced points
+fn curve<P: Vertex<D>, const D: usize>(f: impl Fn(f128) -> P, steps: usize) -> Vec<P::Line> {
+    let point = |i: usize| f(i as f128 / steps as f128);
+    distinct((0..steps).map(|i| [point(i), point(i + 1)]))
+}
+
+/// Lines through `f` sampled on a grid of `u_steps` by `v_steps` cells over the unit square
+fn surface<P: Vertex<D>, const D: usize>(
+    f: impl Fn(f128, f128) -> P,
+    u_steps: usize,
+    v_steps: usize,
+) -> Vec<P::Line> {
+    let point = |i: usize, j: usize| f(i as f128 / u_steps as f128, j as f128 / v_steps as f128);
+    let lines = (0..=u_steps).cartesian_product(0..=v_steps).flat_map(|(i, j)| {
+        let along_u = (i < u_steps).then(|| [point(i, j), point(i + 1, j)]);
+        let along_v = (j < v_steps).then(|| [point(i, j), point(i, j + 1)]);
+        along_u.into_iter().chain(along_v)
+    });
+    distinct(lines)
+}
+
+/// Every point made by permuting the coordinates and flipping the signs of nonzero ones.
+/// `even` restricts the permutations to even ones
+fn expand<P: Vertex<D>, const D: usize>(coords: [f128; D], even: bool) -> Vec<P> {
+    let mut seen = HashSet::new();
+    (0..D)
+        .permutations(D)
+        .filter(|perm| !even || is_even(perm))
+        .cartesian_product(0..1 << D)
+        .map(|(perm, signs)| {
+            P::new(std::array::from_fn(|i| {
+                let sign = if signs & (1 << i) == 0 { 1. } else { -1. };
+                coords[perm[i]] * sign
+            }))
+        })
+        .filter(|point| seen.insert(point.grid_key()))
+        .collect()
+}
+
+fn is_even(perm: &[usize]) -> bool {
+    let inversions = perm
+        .iter()
+        .tuple_combinations()
+        .filter(|(a, b)| a > b)
+        .count();
+    inversions % 2 == 0
+}
+
+/// Drops lines with both ends at the same point, and repeats of a line in either direction
+fn distinct<P: Vertex<D>, const D: usize>(lines: impl Iterator<Item = [P; 2]>) -> Vec<P::Line> {
+    let mut seen = HashSet::new();
+    lines
+        .filter(|[a, b]| {
+            let (a, b) = (a.grid_key(), b.grid_key());
+            a != b && seen.insert((a.min(b), a.max(b)))
+        })
+        .map(|[a, b]| a.join(b))
+        .collect()
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::Shape;
+    use crate::point::Point;
+
+    fn counts(shape: Shape) -> (usize, usize) {
+        (shape.vertices().len(), shape.len())
+    }
+
+    #[test]
+    fn platonic_solids() {
+        assert_eq!(counts(Shape::tetrahedron()), (4, 6));
+        assert_eq!(counts(Shape::cube()), (8, 12));
+        assert_eq!(counts(Shape::octahedron()), (6, 12));
+        assert_eq!(counts(Shape::dodecahedron()), (20, 30));
+        assert_eq!(counts(Shape::icosahedron()), (12, 30));
+    }
+
+    #[test]
+    fn archimedean_solids() {
+        assert_eq!(counts(Shape::truncated_tetrahedron()), (12, 18));
+        assert_eq!(counts(Shape::cuboctahedron()), (12, 24));
+        assert_eq!(counts(Shape::truncated_cube()), (24, 36));
+        assert_eq!(counts(Shape::truncated_octahedron()), (24, 36));
+        assert_eq!(counts(Shape::rhombicuboctahedron()), (24, 48));
+        assert_eq!(counts(Shape::truncated_cuboctahedron()), (48, 72));
+        assert_eq!(counts(Shape::icosidodecahedron()), (30, 60));
+        assert_eq!(counts(Shape::truncated_icosahedron()), (60, 90));
+    }
+
+    #[test]
+    fn surfaces() {
+        assert_eq!(counts(Shape::torus(8, 4)), (32, 64));
+        // Two poles, with 3 rings of 8 between them
+        assert_eq!(counts(Shape::sphere(8, 4)), (26, 56));
+        // The seam joins each end of the strip to the other end flipped over
+        assert_eq!(counts(Shape::mobius_strip(8, 2)), (24, 40));
+        assert_eq!(counts(Shape::klein_bottle(8, 5)), (40, 80));
+        assert_eq!(counts(Shape::curve(|t| Point([t, t * t, 0.]), 10)), (11, 10));
+    }
 }
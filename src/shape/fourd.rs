@@ -1,9 +1,16 @@
-use std::ops::{Deref, DerefMut, Mul};
+use std::{
+    collections::HashSet,
+    f128::consts::{FRAC_1_SQRT_2, FRAC_PI_2, TAU},
+    ops::{Deref, DerefMut, Mul},
+};
 
 use itertools::Itertools;
 
-use super::Shape as Shape3d;
-use crate::{line::fourd::Line, point::fourd::Point};
+use super::{curve, expand, nearest_pairs, surface, Shape as Shape3d, PHI};
+use crate::{
+    line::fourd::Line,
+    point::{fourd::Point, Point as Point3d},
+};
 
 #[derive(Debug, Default, Clone)]
 pub struct Shape(pub Vec<Line>, pub char);
@@ -34,12 +41,12 @@ impl Mul<f128> for Shape {
 }
 
 impl Shape {
-    /// Turns a 4d shape into a 3d shape
+    /// Turns a 4d shape into a 3d shape, dropping anything behind the eye
     pub fn to_shape_3d(&self) -> Shape3d {
         Shape3d(
             self.0
                 .iter()
-                .map(|line| line.to_line_3d())
+                .filter_map(|line| line.to_line_3d())
                 .collect::<Vec<_>>(),
             self.1,
         )
@@ -111,9 +118,26 @@ impl Shape {
         )
     }
 
+    /// Distinct endpoints of the shape's lines, in order of first appearance
+    pub fn vertices(&self) -> Vec<Point> {
+        let mut seen = HashSet::new();
+        self.0
+            .iter()
+            .flat_map(|line| line.0)
+            .filter(|point| seen.insert(point.grid_key()))
+            .collect()
+    }
+
+    /// Connects every pair of vertices that are the minimum distance apart. This gives the
+    /// edges of any polytope whose edges are all the same length and shorter than any other
+    /// vertex to vertex distance, which includes all the regular polytopes
+    pub fn from_vertices(vertices: &[Point]) -> Shape {
+        Shape(nearest_pairs(vertices), '*')
+    }
+
     pub fn fivecell() -> Shape {
         let sqrt_5 = f128::sqrt(5.);
-        let coords = vec![
+        let coords = [
             Point([ sqrt_5,  sqrt_5,  sqrt_5, -1.].map(|x| x * 0.25)),
             Point([ sqrt_5, -sqrt_5, -sqrt_5, -1.].map(|x| x * 0.25)),
             Point([-sqrt_5,  sqrt_5, -sqrt_5, -1.].map(|x| x * 0.25)),
@@ -172,4 +196,187 @@ impl Shape {
             '*',
         )
     }
+
+    /// Samples `f` along the unit interval at `steps` + 1 evenly spaced points, joined in order
+    pub fn curve(f: impl Fn(f128) -> Point, steps: usize) -> Shape {
+        Shape(curve(f, steps), '*')
+    }
+
+    /// Samples `f` over the unit square on a grid of `u_steps` by `v_steps` cells, joining each
+    /// sample to its neighbours along u and v, and keeping lines that coincide only once
+    pub fn surface(f: impl Fn(f128, f128) -> Point, u_steps: usize, v_steps: usize) -> Shape {
+        Shape(surface(f, u_steps, v_steps), '*')
+    }
+
+    /// The flat torus made of two circles of radius sqrt(1/2), lying on the unit 3-sphere
+    pub fn clifford_torus(u_steps: usize, v_steps: usize) -> Shape {
+        Shape::surface(
+            |u, v| {
+                let (u, v) = (u * TAU, v * TAU);
+                Point([u.cos(), u.sin(), v.cos(), v.sin()].map(|x| x * FRAC_1_SQRT_2))
+            },
+            u_steps,
+            v_steps,
+        )
+    }
+
+    /// Great circles of the unit 3-sphere that the Hopf map sends to single points of the
+    /// 2-sphere. `rings` circles of latitude on the 2-sphere each have `fibres` evenly spaced
+    /// points, and every fibre is drawn with `steps` lines. Any two fibres are linked
+    pub fn hopf_fibration(rings: usize, fibres: usize, steps: usize) -> Shape {
+        let lines = (1..=rings)
+            .cartesian_product(0..fibres)
+            .flat_map(|(ring, fibre)| {
+                let eta = FRAC_PI_2 * ring as f128 / (rings + 1) as f128;
+                let phase = TAU * fibre as f128 / fibres as f128;
+                Shape::curve(
+                    |t| {
+                        let t = t * TAU;
+                        Point([
+                            eta.sin() * (t + phase).cos(),
+                            eta.sin() * (t + phase).sin(),
+                            eta.cos() * t.cos(),
+                            eta.cos() * t.sin(),
+                        ])
+                    },
+                    steps,
+                )
+                .0
+            });
+        Shape(lines.collect_vec(), '*')
+    }
+
+    /// Also known as the hexadecachoron or 4-orthoplex, with circumradius 1
+    pub fn sixteencell() -> Shape {
+        Shape::from_vertices(&expand([1., 0., 0., 0.], false))
+    }
+
+    /// Also known as the icositetrachoron, with circumradius sqrt(2)
+    pub fn twentyfourcell() -> Shape {
+        Shape::from_vertices(&expand([1., 1., 0., 0.], false))
+    }
+
+    /// Also known as the hecatonicosachoron, with circumradius 2
+    pub fn hundredtwentycell() -> Shape {
+        let sqrt_5 = f128::sqrt(5.);
+        let phi_inv = 1. / PHI;
+        let phi_inv_2 = phi_inv * phi_inv;
+        let phi_2 = PHI * PHI;
+        let vertices: Vec<Point> = [
+            expand([0., 0., 2., 2.], false),
+            expand([1., 1., 1., sqrt_5], false),
+            expand([phi_inv_2, PHI, PHI, PHI], false),
+            expand([phi_inv, phi_inv, phi_inv, phi_2], false),
+            expand([0., phi_inv_2, 1., phi_2], true),
+            expand([0., phi_inv, PHI, sqrt_5], true),
+            expand([phi_inv, 1., PHI, 2.], true),
+        ]
+        .concat();
+        let vertices = vertices
+            .into_iter()
+            .map(|point| point * FRAC_1_SQRT_2)
+            .collect_vec();
+        Shape::from_vertices(&vertices)
+    }
+
+    /// Also known as the hexacosichoron, with circumradius 1
+    pub fn sixhundredcell() -> Shape {
+        let vertices = [
+            expand([0.5, 0.5, 0.5, 0.5], false),
+            expand([1., 0., 0., 0.], false),
+            expand([PHI / 2., 0.5, 0.5 / PHI, 0.], true),
+        ]
+        .concat();
+        Shape::from_vertices(&vertices)
+    }
+
+    /// The Cartesian product of two lower dimensional shapes. Each point takes its first `dims`
+    /// coordinates from `first` and the rest from the start of `second`, so two polygons in the
+    /// xy plane with `dims` 2 give a duoprism, and a polyhedron and a segment along the x axis
+    /// with `dims` 3 give a prism. Every edge of either shape is copied to every vertex of the
+    /// other
+    pub fn product(first: &Shape3d, dims: usize, second: &Shape3d) -> Shape {
+        assert!((1..4).contains(&dims), "a product needs 1 to 3 coordinates from each shape");
+        let join = |a: Point3d, b: Point3d| {
+            Point(std::array::from_fn(|i| if i < dims { a[i] } else { b[i - dims] }))
+        };
+        let first_edges = first
+            .iter()
+            .cartesian_product(second.vertices())
+            .map(|(line, vertex)| Line(line.map(|point| join(point, vertex))));
+        let second_edges = first
+            .vertices()
+            .into_iter()
+            .cartesian_product(second.iter())
+            .map(|(vertex, line)| Line(line.map(|point| join(vertex, point))));
+        Shape(first_edges.chain(second_edges).collect_vec(), first.1)
+    }
+
+    /// The product of a `p`-sided and a `q`-sided regular polygon, with unit edges
+    pub fn duoprism(p: usize, q: usize) -> Shape {
+        Shape::product(&Shape3d::polygon(p), 2, &Shape3d::polygon(q))
+    }
+
+    /// Extrudes a polyhedron along w by its shortest edge length, so a uniform polyhedron gives
+    /// a uniform prism. The prism of a cube is a hypercube
+    pub fn prism(base: &Shape3d) -> Shape {
+        let height = base
+            .iter()
+            .map(|line| (line[1] - line[0]).l2_norm())
+            .fold(f128::INFINITY, f128::min);
+        Shape::product(base, 3, &Shape3d::segment(height))
+    }
+
+    pub fn tetrahedral_prism() -> Shape {
+        Shape::prism(&Shape3d::tetrahedron())
+    }
+
+    pub fn dodecahedral_prism() -> Shape {
+        Shape::prism(&Shape3d::dodecahedron())
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{Shape, Shape3d};
+
+    fn counts(shape: Shape) -> (usize, usize) {
+        (shape.vertices().len(), shape.len())
+    }
+
+    #[test]
+    fn regular_polytopes() {
+        assert_eq!(counts(Shape::fivecell()), (5, 10));
+        assert_eq!(counts(Shape::hypercube()), (16, 32));
+        assert_eq!(counts(Shape::sixteencell()), (8, 24));
+        assert_eq!(counts(Shape::twentyfourcell()), (24, 96));
+        assert_eq!(counts(Shape::sixhundredcell()), (120, 720));
+        assert_eq!(counts(Shape::hundredtwentycell()), (600, 1200));
+    }
+
+    #[test]
+    fn products() {
+        assert_eq!(counts(Shape::duoprism(3, 4)), (12, 24));
+        assert_eq!(counts(Shape::duoprism(5, 7)), (35, 70));
+        assert_eq!(counts(Shape::tetrahedral_prism()), (8, 16));
+        assert_eq!(counts(Shape::dodecahedral_prism()), (40, 80));
+
+        let keys = |shape: Shape| {
+            let mut keys = shape.vertices().iter().map(|point| point.grid_key()).collect::<Vec<_>>();
+            keys.sort();
+            keys
+        };
+        assert_eq!(keys(Shape::prism(&Shape3d::cube())), keys(Shape::hypercube()));
+        assert_eq!(Shape::prism(&Shape3d::cube()).len(), 32);
+    }
+
+    #[test]
+    fn surfaces() {
+        assert_eq!(counts(Shape::clifford_torus(8, 6)), (48, 96));
+        // Fibres never meet, so each is its own loop
+        assert_eq!(counts(Shape::hopf_fibration(2, 3, 8)), (48, 48));
+        for point in Shape::hopf_fibration(3, 5, 12).vertices() {
+            assert!((point.l2_norm() - 1.).abs() < 1e-20);
+        }
+    }
 }
@@ -0,0 +1,222 @@
+use serde::Deserialize;
+
+use crate::{
+    colour::Colour,
+    rotation::{fourd::Rotation, Quaternion},
+};
+
+/// How a change between two keyframes speeds up and slows down
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Easing {
+    /// At a steady rate
+    #[default]
+    Linear,
+    /// Starting slowly
+    EaseIn,
+    /// Stopping slowly
+    EaseOut,
+    /// Starting and stopping slowly
+    EaseInOut,
+    /// Holding the earlier value until the later keyframe, then jumping to it
+    Step,
+}
+
+impl Easing {
+    /// How far through the change to be, from 0 to 1, a fraction t of the way through its time
+    pub fn apply(self, t: f128) -> f128 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1. - (1. - t) * (1. - t) * (1. - t),
+            Easing::EaseInOut if t < 0.5 => 4. * t * t * t,
+            Easing::EaseInOut => 1. - 4. * (1. - t) * (1. - t) * (1. - t),
+            Easing::Step if t < 1. => 0.,
+            Easing::Step => 1.,
+        }
+    }
+}
+
+/// Something with values in between two others
+pub trait Interpolate {
+    /// The value a fraction t of the way from `self` to `end`
+    fn interpolate(&self, end: &Self, t: f128) -> Self;
+}
+
+impl Interpolate for f128 {
+    fn interpolate(&self, end: &Self, t: f128) -> Self {
+        self + (end - self) * t
+    }
+}
+
+impl Interpolate for Colour {
+    fn interpolate(&self, end: &Self, t: f128) -> Self {
+        let mut colour = *self;
+        for (channel, end) in colour.0.iter_mut().zip(end.0) {
+            *channel = (*channel as f128).interpolate(&(end as f128), t).round() as u8;
+        }
+        colour
+    }
+}
+
+/// Which way an object faces, as rotations for both 3d and 4d objects so either can be turned
+/// smoothly from one orientation to another
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Orientation {
+    pub rotation_3d: Quaternion,
+    pub rotation_4d: Rotation,
+}
+
+impl Orientation {
+    /// Turned by radians in the same planes as `Object::spin`. 3d objects only use the first
+    /// three
+    pub fn from_angles(angles: [f128; 6]) -> Orientation {
+        let [xy, xz, yz, ..] = angles;
+        Orientation {
+            rotation_3d: Quaternion::from_angles([xy, xz, yz]),
+            rotation_4d: Rotation::from_angles(angles),
+        }
+    }
+}
+
+impl Interpolate for Orientation {
+    fn interpolate(&self, end: &Self, t: f128) -> Self {
+        Orientation {
+            rotation_3d: self.rotation_3d.slerp_rotation(end.rotation_3d, t),
+            rotation_4d: self.rotation_4d.slerp(end.rotation_4d, t),
+        }
+    }
+}
+
+/// A value at a point in time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Key<T> {
+    /// Frames from the start of the timeline, 20 to a second when animated
+    pub frame: f128,
+    pub value: T,
+    /// How the value changes on the way to this keyframe from the one before
+    pub easing: Easing,
+}
+
+/// A value that changes over time, given by keyframes in order of time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track<T>(pub Vec<Key<T>>);
+
+impl<T> Default for Track<T> {
+    fn default() -> Self {
+        Track(vec![])
+    }
+}
+
+impl<T: Interpolate + Clone> Track<T> {
+    /// Adds a keyframe, keeping the keyframes in order
+    pub fn key(mut self, frame: f128, value: T, easing: Easing) -> Track<T> {
+        let index = self.0.partition_point(|key| key.frame <= frame);
+        self.0.insert(
+            index,
+            Key {
+                frame,
+                value,
+                easing,
+            },
+        );
+        self
+    }
+
+    /// The value at a point in time, holding the first and last values before and after the
+    /// keyframes. An empty track has no value
+    pub fn sample(&self, frame: f128) -> Option<T> {
+        let next = self.0.partition_point(|key| key.frame <= frame);
+        match (self.0.get(next.wrapping_sub(1)), self.0.get(next)) {
+            (Some(before), Some(after)) => {
+                let t = (frame - before.frame) / (after.frame - before.frame);
+                Some(
+                    before
+                        .value
+                        .interpolate(&after.value, after.easing.apply(t)),
+                )
+            }
+            (Some(key), None) | (None, Some(key)) => Some(key.value.clone()),
+            (None, None) => None,
+        }
+    }
+
+    /// The time of the last keyframe
+    pub fn end(&self) -> f128 {
+        self.0.last().map_or(0., |key| key.frame)
+    }
+}
+
+/// Keyframed changes to an object, on top of its own spin
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Timeline {
+    pub orientation: Track<Orientation>,
+    /// Multiplies the object's own scale
+    pub scale: Track<f128>,
+    pub colour: Track<Colour>,
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{Easing, Orientation, Track};
+    use crate::{colour::Colour, rotation::Quaternion};
+
+    #[test]
+    fn easing() {
+        let easings = [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ];
+        for easing in easings {
+            assert_eq!((easing.apply(0.), easing.apply(1.)), (0., 1.));
+            assert!(
+                easing.apply(0.25) < easing.apply(0.5) && easing.apply(0.5) < easing.apply(0.75)
+            );
+        }
+        assert!(Easing::EaseIn.apply(0.5) < 0.5 && Easing::EaseOut.apply(0.5) > 0.5);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert_eq!((Easing::Step.apply(0.99), Easing::Step.apply(1.)), (0., 1.));
+    }
+
+    #[test]
+    fn tracks() {
+        let track = Track::default()
+            .key(20., 3., Easing::Linear)
+            .key(0., 1., Easing::Linear)
+            .key(30., 0., Easing::Step);
+        assert_eq!(
+            [-5., 0., 10., 20., 25., 30., 100.].map(|frame| track.sample(frame).unwrap()),
+            [1., 1., 2., 3., 3., 0., 0.]
+        );
+        assert_eq!(track.end(), 30.);
+        assert_eq!(Track::<f128>::default().sample(0.), None);
+
+        let colours = Track::default()
+            .key(0., Colour([0, 100, 255]), Easing::Linear)
+            .key(10., Colour([255, 100, 0]), Easing::Linear);
+        assert_eq!(colours.sample(5.), Some(Colour([128, 100, 128])));
+
+        // Orientations turn along the shortest way between keyframes
+        let pi = std::f128::consts::PI;
+        let turn = Track::default()
+            .key(0., Orientation::default(), Easing::Linear)
+            .key(
+                10.,
+                Orientation::from_angles([pi / 2., 0., 0., 0., 0., pi / 2.]),
+                Easing::Linear,
+            );
+        let halfway = turn.sample(5.).unwrap();
+        let expected = Orientation::from_angles([pi / 4., 0., 0., 0., 0., pi / 4.]);
+        assert!((halfway.rotation_3d.dot(expected.rotation_3d) - 1.).abs() < 1e-9);
+        let matrices = [halfway, expected].map(|orientation| orientation.rotation_4d.to_matrix().0);
+        assert!(matrices[0]
+            .iter()
+            .flatten()
+            .zip(matrices[1].iter().flatten())
+            .all(|(a, b)| (a - b).abs() < 1e-9));
+        assert_ne!(halfway.rotation_3d, Quaternion::identity());
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
+    str::FromStr,
+};
+
+use crate::{
+    line::{fourd::Line as Line4d, Line},
+    point::{fourd::Point as Point4d, Point},
+    shape::{fourd::Shape as Shape4d, Shape},
+};
+
+/// Stop generating once a polytope has this many vertices, in case of a mistyped diagram
+const MAX_VERTICES: usize = 20_000;
+
+/// Vertex coordinates and the index pairs of the edges between them
+type Skeleton<const D: usize> = (Vec<[f128; D]>, Vec<[usize; 2]>);
+
+/// A linear Coxeter-Dynkin diagram: a row of mirrors, each either ringed or not, joined by
+/// branches giving the angle pi / m between neighbouring mirrors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagram {
+    pub ringed: Vec<bool>,
+    /// The m of each branch, one fewer than there are mirrors. 2 means the mirrors are
+    /// perpendicular, which a drawn diagram shows as no branch at all
+    pub branches: Vec<u32>,
+}
+
+/// A polytope produced by the generator, which may be either 3d or 4d
+#[derive(Debug, Clone)]
+pub enum Polytope {
+    Shape(Shape),
+    Shape4d(Shape4d),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The text was neither a Schläfli symbol nor a diagram
+    Parse(String),
+    /// Only 3d and 4d polytopes can be shown
+    Dimension(usize),
+    /// Nothing is ringed, so every vertex would be the same point
+    NoRings,
+    /// The mirrors generate an infinite group, such as a tiling, or more vertices than
+    /// `MAX_VERTICES`
+    Infinite,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Parse(text) => write!(
+                f,
+                "could not read {text:?}, expected a Schläfli symbol like {{4,3,3}} or a diagram like x4o3o3o"
+            ),
+            Error::Dimension(dim) => write!(f, "only 3d and 4d polytopes are supported, not {dim}d"),
+            Error::NoRings => write!(f, "at least one mirror must be ringed"),
+            Error::Infinite => write!(f, "the diagram does not describe a finite polytope"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl FromStr for Diagram {
+    type Err = Error;
+
+    /// Reads either a Schläfli symbol such as `{3,3,5}`, giving the regular polytope, or a
+    /// diagram such as `x3o3x5o`, where `x` marks a ringed mirror and `o` an unringed one
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || Error::Parse(text.to_string());
+        let text = text.trim();
+        if let Some(inner) = text.strip_prefix('{').and_then(|rest| rest.strip_suffix('}')) {
+            let branches = inner
+                .split(',')
+                .map(|m| m.trim().parse::<u32>().ok().filter(|m| *m >= 2))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(error)?;
+            let mut ringed = vec![false; branches.len() + 1];
+            ringed[0] = true;
+            return Ok(Diagram { ringed, branches });
+        }
+
+        let mut ringed = vec![];
+        let mut branches = vec![];
+        let mut chars = text.chars().peekable();
+        loop {
+            match chars.next() {
+                Some('x') => ringed.push(true),
+                Some('o') => ringed.push(false),
+                _ => return Err(error()),
+            }
+            if chars.peek().is_none() {
+                break;
+            }
+            let mut digits = String::new();
+            while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                digits.push(digit);
+            }
+            branches.push(digits.parse::<u32>().ok().filter(|m| *m >= 2).ok_or_else(error)?);
+        }
+        Ok(Diagram { ringed, branches })
+    }
+}
+
+impl Diagram {
+    pub fn dimension(&self) -> usize {
+        self.ringed.len()
+    }
+
+    /// Builds the uniform polytope described by the diagram
+    pub fn build(&self) -> Result<Polytope, Error> {
+        match self.dimension() {
+            3 => {
+                let (vertices, edges) = self.generate::<3>()?;
+                let lines = edges
+                    .iter()
+                    .map(|[a, b]| Line([Point(vertices[*a]), Point(vertices[*b])]));
+                Ok(Polytope::Shape(Shape(lines.collect(), '*')))
+            }
+            4 => {
+                let (vertices, edges) = self.generate::<4>()?;
+                let lines = edges
+                    .iter()
+                    .map(|[a, b]| Line4d([Point4d(vertices[*a]), Point4d(vertices[*b])]));
+                Ok(Polytope::Shape4d(Shape4d(lines.collect(), '*')))
+            }
+            dim => Err(Error::Dimension(dim)),
+        }
+    }
+
+    /// Unit normals of the mirrors, chosen so the angle between neighbours is pi - pi / m
+    fn roots<const D: usize>(&self) -> Result<[[f128; D]; D], Error> {
+        let gram = |i: usize, j: usize| -> f128 {
+            match i.abs_diff(j) {
+                0 => 1.,
+                1 => -(std::f128::consts::PI / self.branches[i.min(j)] as f128).cos(),
+                _ => 0.,
+            }
+        };
+        // The Cholesky factor of the Gram matrix has rows with exactly those dot products. It
+        // only exists when the Gram matrix is positive definite, which is when the group is
+        // finite
+        let mut roots = [[0.; D]; D];
+        for i in 0..D {
+            for j in 0..=i {
+                let dot = (0..j).map(|k| roots[i][k] * roots[j][k]).sum::<f128>();
+                if i == j {
+                    let square = gram(i, i) - dot;
+                    if square <= 1e-20 {
+                        return Err(Error::Infinite);
+                    }
+                    roots[i][i] = square.sqrt();
+                } else {
+                    roots[i][j] = (gram(i, j) - dot) / roots[j][j];
+                }
+            }
+        }
+        Ok(roots)
+    }
+
+    /// Vertices and edges of the polytope, found by reflecting the edges touching the first
+    /// vertex in every mirror until no new edges appear
+    fn generate<const D: usize>(&self) -> Result<Skeleton<D>, Error> {
+        if !self.ringed.iter().any(|ringed| *ringed) {
+            return Err(Error::NoRings);
+        }
+        let roots = self.roots::<D>()?;
+        let dot = |a: &[f128; D], b: &[f128; D]| (0..D).map(|i| a[i] * b[i]).sum::<f128>();
+        let reflect = |point: &[f128; D], root: &[f128; D]| {
+            let scale = 2. * dot(point, root);
+            std::array::from_fn(|i| point[i] - scale * root[i])
+        };
+
+        // The first vertex is 1 from each ringed mirror and on each unringed one. The roots are
+        // lower triangular, so this is a forward substitution
+        let mut first = [0.; D];
+        for i in 0..D {
+            let distance = if self.ringed[i] { 1. } else { 0. };
+            let dot = (0..i).map(|k| roots[i][k] * first[k]).sum::<f128>();
+            first[i] = (distance - dot) / roots[i][i];
+        }
+
+        let mut vertices = vec![];
+        let mut indices = HashMap::new();
+        let mut index_of = |point: [f128; D]| -> Result<usize, Error> {
+            let key = point.map(|x| (x * 1e9).round() as i64);
+            if let Some(index) = indices.get(&key) {
+                return Ok(*index);
+            }
+            if vertices.len() >= MAX_VERTICES {
+                return Err(Error::Infinite);
+            }
+            vertices.push(point);
+            indices.insert(key, vertices.len() - 1);
+            Ok(vertices.len() - 1)
+        };
+
+        let mut edges = vec![];
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        for (root, ringed) in roots.iter().zip(&self.ringed) {
+            if *ringed {
+                queue.push_back([first, reflect(&first, root)]);
+            }
+        }
+        while let Some([a, b]) = queue.pop_front() {
+            let (i, j) = (index_of(a)?, index_of(b)?);
+            if !seen.insert([i.min(j), i.max(j)]) {
+                continue;
+            }
+            edges.push([i, j]);
+            for root in &roots {
+                queue.push_back([reflect(&a, root), reflect(&b, root)]);
+            }
+        }
+        Ok((vertices, edges))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{Diagram, Error, Polytope};
+
+    fn counts(text: &str) -> (usize, usize) {
+        match text.parse::<Diagram>().unwrap().build().unwrap() {
+            Polytope::Shape(shape) => (shape.vertices().len(), shape.len()),
+            Polytope::Shape4d(shape) => (shape.vertices().len(), shape.len()),
+        }
+    }
+
+    #[test]
+    fn regular() {
+        assert_eq!(counts("{3,3}"), (4, 6));
+        assert_eq!(counts("{4,3}"), (8, 12));
+        assert_eq!(counts("{5,3}"), (20, 30));
+        assert_eq!(counts("{3,3,3}"), (5, 10));
+        assert_eq!(counts("{4,3,3}"), (16, 32));
+        assert_eq!(counts("{3,4,3}"), (24, 96));
+        assert_eq!(counts("{3,3,5}"), (120, 720));
+        assert_eq!(counts("{5,3,3}"), (600, 1200));
+    }
+
+    #[test]
+    fn uniform() {
+        // Truncated icosahedron, cuboctahedron and rectified 5-cell
+        assert_eq!(counts("x5x3o"), (60, 90));
+        assert_eq!(counts("o4x3o"), (12, 24));
+        assert_eq!(counts("o3x3o3o"), (10, 30));
+        // Hexagonal prism and cube prism, using a perpendicular branch
+        assert_eq!(counts("x3x2x"), (12, 18));
+        assert_eq!(counts("x4o3o2x"), (16, 32));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!("x3q".parse::<Diagram>(), Err(Error::Parse(_))));
+        assert_eq!("{6,3}".parse::<Diagram>().unwrap().build().unwrap_err(), Error::Infinite);
+        assert_eq!("o3o3o".parse::<Diagram>().unwrap().build().unwrap_err(), Error::NoRings);
+        assert_eq!("{5}".parse::<Diagram>().unwrap().build().unwrap_err(), Error::Dimension(2));
+    }
+}
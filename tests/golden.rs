@@ -0,0 +1,133 @@
+#![feature(f128)]
+
+//! Renders built-in shapes at fixed orientations and compares the frames with the text files in
+//! `tests/golden`. Run with `CUBERS_BLESS=1` to write the current frames as the new goldens.
+
+use std::{env, fs, path::PathBuf};
+
+use cubers::{
+    mesh::{fourd::Mesh as Mesh4d, Mesh},
+    point::{fourd::Point as Point4d, Point},
+    scene::{Object, Scene},
+    screen::Screen,
+    shape::{fourd::Shape as Shape4d, Shape},
+};
+
+type Frame = Screen<78, 42>;
+
+/// A shape to draw and the name of its golden file
+type Named<T> = (&'static str, fn() -> T);
+
+/// How a 3d shape is turned before it is drawn, so no face is seen edge on
+fn pose_3d(shape: Shape) -> Mesh {
+    Mesh::from(shape).rotate_x_theta(0.3).rotate_y_theta(0.5).rotate_z_theta(0.2)
+}
+
+/// How a 4d shape is turned before it is projected, so it is not seen along an axis
+fn pose_4d(shape: Shape4d) -> Mesh {
+    Mesh4d::from(shape)
+        .rotate_xw_theta(0.3)
+        .rotate_zw_theta(0.5)
+        .rotate_xy_theta(0.2)
+        .rotate_yz_theta(0.1)
+        .to_mesh_3d()
+}
+
+/// Draws a mesh scaled to fill the screen
+fn render(mut mesh: Mesh) -> String {
+    let mut screen = Frame::default();
+    mesh.glyph = '*';
+    let scale = screen.fit_scale(std::slice::from_ref(&mesh));
+    screen.render_mesh(&(mesh * scale));
+    screen.to_string()
+}
+
+fn frames() -> Vec<(&'static str, String)> {
+    let shapes: [Named<Shape>; 19] = [
+        ("cube", Shape::cube),
+        ("tetrahedron", Shape::tetrahedron),
+        ("octahedron", Shape::octahedron),
+        ("dodecahedron", Shape::dodecahedron),
+        ("icosahedron", Shape::icosahedron),
+        ("truncated_tetrahedron", Shape::truncated_tetrahedron),
+        ("cuboctahedron", Shape::cuboctahedron),
+        ("truncated_cube", Shape::truncated_cube),
+        ("truncated_octahedron", Shape::truncated_octahedron),
+        ("rhombicuboctahedron", Shape::rhombicuboctahedron),
+        ("truncated_cuboctahedron", Shape::truncated_cuboctahedron),
+        ("icosidodecahedron", Shape::icosidodecahedron),
+        ("truncated_icosahedron", Shape::truncated_icosahedron),
+        ("hexagon", || Shape::polygon(6)),
+        ("torus", || Shape::torus(24, 8)),
+        ("sphere", || Shape::sphere(16, 8)),
+        ("klein_bottle", || Shape::klein_bottle(24, 12)),
+        ("mobius_strip", || Shape::mobius_strip(24, 3)),
+        ("helix", || {
+            Shape::curve(|t| Point([(t * 20.).cos(), (t * 20.).sin(), 2. * t - 1.]), 120)
+        }),
+    ];
+    let shapes_4d: [Named<Shape4d>; 11] = [
+        ("fivecell", Shape4d::fivecell),
+        ("hypercube", Shape4d::hypercube),
+        ("sixteencell", Shape4d::sixteencell),
+        ("twentyfourcell", Shape4d::twentyfourcell),
+        ("sixhundredcell", Shape4d::sixhundredcell),
+        ("hundredtwentycell", Shape4d::hundredtwentycell),
+        ("duoprism_3_4", || Shape4d::duoprism(3, 4)),
+        ("tetrahedral_prism", Shape4d::tetrahedral_prism),
+        ("dodecahedral_prism", Shape4d::dodecahedral_prism),
+        ("clifford_torus", || Shape4d::clifford_torus(16, 16)),
+        ("hopf_fibration", || Shape4d::hopf_fibration(3, 6, 24)),
+    ];
+
+    let mut frames = shapes
+        .into_iter()
+        .map(|(name, shape)| (name, render(pose_3d(shape()))))
+        .chain(shapes_4d.into_iter().map(|(name, shape)| (name, render(pose_4d(shape())))))
+        .collect::<Vec<_>>();
+
+    // A cross-section through the hypercube, tilted so the section is a hexagonal prism
+    let hypercube = Mesh4d::convex_hull(&Shape4d::hypercube().vertices()).unwrap();
+    let section = hypercube.slice(Point4d([1., 1., 1., 0.]), 0.);
+    frames.push(("hypercube_section", render(section.rotate_x_theta(0.3).rotate_y_theta(0.5))));
+
+    // Several objects with their own glyphs, drawn in one pass
+    let scene = Scene::default()
+        .with(Object {
+            position: Point([-2.2, 0., 0.]),
+            ..Object::new(Shape::cube()).with_glyph('#')
+        })
+        .with(Object {
+            scale: 2.,
+            position: Point([2.2, 0., 0.]),
+            ..Object::new(Shape4d::fivecell()).with_glyph('+')
+        });
+    let mut screen = Frame::default();
+    let scale = screen.fit_scale(&[scene.combined()]);
+    frames.push(("scene", screen.render_to_string(&scene, scale)));
+    frames
+}
+
+#[test]
+fn golden_frames() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden");
+    let bless = env::var_os("CUBERS_BLESS").is_some();
+    let mut failures = vec![];
+    for (name, frame) in frames() {
+        let path = dir.join(format!("{name}.txt"));
+        if bless {
+            fs::write(&path, &frame).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&path) {
+            Ok(golden) if golden == frame => (),
+            Ok(golden) => failures.push(format!("{name} changed. Expected:\n{golden}\nFound:\n{frame}")),
+            Err(err) => failures.push(format!("{name}: cannot read {}: {err}", path.display())),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{}\nIf these changes are intended, run again with CUBERS_BLESS=1",
+        failures.join("\n")
+    );
+}